@@ -0,0 +1,56 @@
+use rand::Rng;
+
+use crate::char_type::CharType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alphabet {
+    Mixed, // Numbers and letters with equal odds
+    Digits,
+    Letters,
+    Custom(Vec<CharType>),
+}
+
+impl Alphabet {
+    // Accepts "mixed", "digits", "letters" or the characters to draw from, e.g. "ABC123"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "mixed" => return Ok(Alphabet::Mixed),
+            "digits" => return Ok(Alphabet::Digits),
+            "letters" => return Ok(Alphabet::Letters),
+            _ => {}
+        }
+
+        let mut items: Vec<CharType> = vec![];
+        for c in text.chars() {
+            let item = CharType::from_char(c.to_ascii_uppercase())
+                .ok_or_else(|| format!("'{}' can't be typed in the game, use 0-9 and A-Z", c))?;
+            if !items.contains(&item) {
+                items.push(item);
+            }
+        }
+
+        // N-back foils and recognition substitutes need something to differ from
+        if items.len() < 2 {
+            return Err("a custom alphabet needs at least two different characters".to_string());
+        }
+        Ok(Alphabet::Custom(items))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Alphabet::Mixed => "mixed".to_string(),
+            Alphabet::Digits => "digits".to_string(),
+            Alphabet::Letters => "letters".to_string(),
+            Alphabet::Custom(items) => items.iter().map(|c| c.as_char()).collect(),
+        }
+    }
+
+    pub fn random_item<R: Rng>(&self, rng: &mut R) -> CharType {
+        match self {
+            Alphabet::Mixed => CharType::random(rng),
+            Alphabet::Digits => CharType::Number(rng.gen_range(b'0'..=b'9')),
+            Alphabet::Letters => CharType::Letter(rng.gen_range(b'A'..=b'Z')),
+            Alphabet::Custom(items) => items[rng.gen_range(0..items.len())].clone(),
+        }
+    }
+}
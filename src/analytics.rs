@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::game_mode::GameMode;
+use crate::input_direction::InputDirection;
+use crate::recall_test::TestVariant;
+use crate::replay::Action;
+use crate::round_result::{format_date, RoundResult};
+
+// Charts built from the round history, each with the numbers behind it as CSV
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub key: &'static str, // File name of the export
+    pub title: &'static str,
+    pub x_label: &'static str,
+    pub y_label: &'static str,
+    pub x_names: Option<Vec<String>>, // For categories such as sessions, indexed by x
+    pub y_max: Option<f64>,           // Fixed top of the axis, e.g. 1 for accuracy
+    pub series: Vec<Series>,
+}
+
+impl Chart {
+    pub fn is_empty(&self) -> bool {
+        self.series.iter().all(|series| series.points.is_empty())
+    }
+
+    pub fn x_name(&self, x: f64) -> String {
+        match &self.x_names {
+            Some(names) => names.get(x as usize).cloned().unwrap_or_default(),
+            None => format!("{}", x),
+        }
+    }
+
+    // One row per point: series, x, y
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("series,{},{}\n", csv_field(self.x_label), csv_field(self.y_label));
+        for series in &self.series {
+            for (x, y) in &series.points {
+                csv.push_str(&format!("{},{},{}\n", csv_field(&series.name), csv_field(&self.x_name(*x)), y));
+            }
+        }
+        csv
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Rounds recalled item by item in a required order, which is what the charts look at
+fn is_serial_recall(result: &RoundResult) -> bool {
+    match result.game_mode {
+        GameMode::Sequence => result.test_variant == TestVariant::SerialRecall,
+        GameMode::SpatialSpan | GameMode::ComplexSpan => true,
+        GameMode::NBack | GameMode::DualNBack => false,
+    }
+}
+
+// Sessions are the ones set up for a study, otherwise the days rounds were played on
+fn session_name(result: &RoundResult) -> String {
+    match &result.session {
+        Some(session) => format!("{} #{}", session.participant_id, session.session),
+        None => format_date(result.timestamp),
+    }
+}
+
+// Rounds grouped by session, in the order the sessions started
+fn by_session(history: &[RoundResult]) -> (Vec<String>, Vec<Vec<&RoundResult>>) {
+    let mut names: Vec<String> = vec![];
+    let mut groups: Vec<Vec<&RoundResult>> = vec![];
+    let mut sorted: Vec<&RoundResult> = history.iter().collect();
+    sorted.sort_by_key(|result| result.timestamp);
+    for result in sorted {
+        let name = session_name(result);
+        match names.iter().position(|other| *other == name) {
+            Some(index) => groups[index].push(result),
+            None => {
+                names.push(name);
+                groups.push(vec![result]);
+            }
+        }
+    }
+    (names, groups)
+}
+
+// Share of items recalled in their place, by the position they were shown in
+pub fn serial_position(history: &[RoundResult]) -> Chart {
+    let mut series = vec![];
+    for direction in [InputDirection::Forward, InputDirection::Reverse] {
+        let mut tallies: BTreeMap<usize, (u32, u32)> = BTreeMap::new(); // Correct, attempted
+        for result in history.iter().filter(|result| is_serial_recall(result) && result.direction == direction) {
+            let sequence: Vec<char> = result.sequence.chars().collect();
+            let input: Vec<char> = result.input.chars().collect();
+            for (position, item) in sequence.iter().enumerate() {
+                let slot = match direction {
+                    InputDirection::Reverse => sequence.len() - position - 1,
+                    _ => position,
+                };
+                // Strict rounds end at the first mistake, so later items were never tried
+                if result.strict && slot >= input.len() {
+                    continue;
+                }
+                let tally = tallies.entry(position + 1).or_default();
+                tally.1 += 1;
+                if input.get(slot) == Some(item) {
+                    tally.0 += 1;
+                }
+            }
+        }
+        series.push(Series {
+            name: direction.short_name().to_string(),
+            points: tallies.into_iter().map(|(position, (correct, tried))| (position as f64, correct as f64 / tried as f64)).collect(),
+        });
+    }
+    Chart {
+        key: "serial_position",
+        title: "Accuracy by serial position",
+        x_label: "position",
+        y_label: "accuracy",
+        x_names: None,
+        y_max: Some(1.0),
+        series,
+    }
+}
+
+// Longest correctly recalled length and mean score, per session
+pub fn sessions(history: &[RoundResult]) -> (Chart, Chart) {
+    let (names, groups) = by_session(history);
+    let mut span = vec![];
+    let mut score = vec![];
+    for (index, group) in groups.iter().enumerate() {
+        let longest = group
+            .iter()
+            .filter(|result| is_serial_recall(result) && result.success)
+            .map(|result| result.length())
+            .max();
+        if let Some(longest) = longest {
+            span.push((index as f64, longest as f64));
+        }
+        score.push((index as f64, group.iter().map(|result| result.score as f64).sum::<f64>() / group.len() as f64));
+    }
+    (
+        Chart {
+            key: "span_by_session",
+            title: "Span by session",
+            x_label: "session",
+            y_label: "span",
+            x_names: Some(names.clone()),
+            y_max: None,
+            series: vec![Series { name: "Span".to_string(), points: span }],
+        },
+        Chart {
+            key: "score_by_session",
+            title: "Mean score by session",
+            x_label: "session",
+            y_label: "score",
+            x_names: Some(names),
+            y_max: None,
+            series: vec![Series { name: "Score".to_string(), points: score }],
+        },
+    )
+}
+
+// Mean time between one entered item and the next, by the position of the later one. Rounds
+// with deletions are left out, as the positions no longer line up.
+pub fn inter_key_intervals(history: &[RoundResult]) -> Chart {
+    let mut series = vec![];
+    for direction in InputDirection::ALL {
+        let mut intervals: BTreeMap<usize, Vec<u64>> = BTreeMap::new();
+        for result in history.iter().filter(|result| is_serial_recall(result) && result.direction == direction) {
+            if result.keystrokes.iter().any(|keystroke| keystroke.action == Action::Delete) {
+                continue;
+            }
+            // The first entry has nothing before it to time from
+            let entries: Vec<u64> = result
+                .keystrokes
+                .iter()
+                .filter(|keystroke| keystroke.item.is_none() && matches!(keystroke.action, Action::Item(_)))
+                .map(|keystroke| keystroke.ms)
+                .collect();
+            for (index, pair) in entries.windows(2).enumerate() {
+                intervals.entry(index + 2).or_default().push(pair[1].saturating_sub(pair[0]));
+            }
+        }
+        if intervals.is_empty() {
+            continue;
+        }
+        series.push(Series {
+            name: direction.short_name().to_string(),
+            points: intervals
+                .into_iter()
+                .map(|(position, times)| (position as f64, times.iter().sum::<u64>() as f64 / times.len() as f64))
+                .collect(),
+        });
+    }
+    Chart {
+        key: "inter_key_interval",
+        title: "Mean inter-key interval by position",
+        x_label: "position",
+        y_label: "ms",
+        x_names: None,
+        y_max: None,
+        series,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ErrorCounts {
+    pub transpositions: u32, // An item from the list in the wrong place
+    pub intrusions: u32,     // An item that wasn't in the list
+    pub omissions: u32,      // Places left empty
+}
+
+pub fn classify_errors(result: &RoundResult) -> ErrorCounts {
+    let sequence: Vec<char> = result.sequence.chars().collect();
+    let expected = result.direction.arrange(&sequence, |c| *c);
+    let input: Vec<char> = result.input.chars().collect();
+    let mut counts = ErrorCounts::default();
+    for (slot, item) in expected.iter().enumerate() {
+        match input.get(slot) {
+            Some(entered) if entered == item => {}
+            Some(entered) if sequence.contains(entered) => counts.transpositions += 1,
+            Some(_) => counts.intrusions += 1,
+            // A strict round stopping early isn't the player leaving places out
+            None if !result.strict => counts.omissions += 1,
+            None => {}
+        }
+    }
+    counts
+}
+
+// Errors of each type per round, per session
+pub fn error_types(history: &[RoundResult]) -> Chart {
+    let (names, groups) = by_session(history);
+    let mut series: Vec<Series> = ["Transpositions", "Intrusions", "Omissions"]
+        .into_iter()
+        .map(|name| Series { name: name.to_string(), points: vec![] })
+        .collect();
+    for (index, group) in groups.iter().enumerate() {
+        let rounds: Vec<&&RoundResult> = group.iter().filter(|result| is_serial_recall(result)).collect();
+        if rounds.is_empty() {
+            continue;
+        }
+        let counts: Vec<ErrorCounts> = rounds.iter().map(|result| classify_errors(result)).collect();
+        let mean = |count: fn(&ErrorCounts) -> u32| counts.iter().map(count).sum::<u32>() as f64 / counts.len() as f64;
+        series[0].points.push((index as f64, mean(|counts| counts.transpositions)));
+        series[1].points.push((index as f64, mean(|counts| counts.intrusions)));
+        series[2].points.push((index as f64, mean(|counts| counts.omissions)));
+    }
+    Chart {
+        key: "error_types",
+        title: "Errors per round by session",
+        x_label: "session",
+        y_label: "errors per round",
+        x_names: Some(names),
+        y_max: None,
+        series,
+    }
+}
+
+pub fn all_charts(history: &[RoundResult]) -> Vec<Chart> {
+    let (span, score) = sessions(history);
+    vec![serial_position(history), span, score, inter_key_intervals(history), error_types(history)]
+}
+
+// Writes each chart's numbers to <key>.csv in the directory
+pub fn export(charts: &[Chart], dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+    for chart in charts {
+        let path = dir.join(format!("{}.csv", chart.key));
+        fs::write(&path, chart.to_csv()).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use memory_game::leaderboard_server::LeaderboardServer;
+
+const USAGE: &str = "\
+Usage: leaderboard_server [OPTIONS]
+
+Options:
+  --bind ADDRESS   Address to listen on (default 127.0.0.1:7878, use 0.0.0.0:7878 for the whole network)
+  --file FILE      Where submitted rounds are kept as JSON lines (default leaderboard.jsonl)
+  -h, --help       Show this help";
+
+fn main() {
+    let mut bind = "127.0.0.1:7878".to_string();
+    let mut file = PathBuf::from("leaderboard.jsonl");
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" | "--file" => {
+                let Some(value) = args.next() else {
+                    exit_with_usage(&format!("{} needs a value", arg));
+                };
+                if arg == "--bind" {
+                    bind = value;
+                } else {
+                    file = PathBuf::from(value);
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => exit_with_usage(&format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    let mut server = match LeaderboardServer::open(file.clone()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("leaderboard_server: could not read {}: {}", file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let listener = match TcpListener::bind(&bind) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("leaderboard_server: could not listen on {}: {}", bind, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Leaderboard server listening on {}, keeping rounds in {}", bind, file.display());
+    server.serve(listener);
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("leaderboard_server: {}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
@@ -0,0 +1,199 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use memory_game::input_direction::InputDirection;
+use memory_game::recall_test::TestVariant;
+use memory_game::{GamePhase, MemoryGame};
+
+// Roughly the GUI frame rate, so the presentation timer advances just as smoothly
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+type Line = (String, Option<Color>);
+
+fn main() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    // A panic would otherwise leave the shell in raw mode on the alternate screen
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+
+    let result = run(&mut stdout);
+
+    restore_terminal()?;
+    result
+}
+
+fn restore_terminal() -> io::Result<()> {
+    execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()
+}
+
+fn run(stdout: &mut io::Stdout) -> io::Result<()> {
+    let mut game = MemoryGame::new();
+    let mut last_frame = Instant::now();
+    let mut shown: Vec<Line> = vec![];
+
+    loop {
+        if event::poll(FRAME_TIME)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Esc => return Ok(()),
+                // Raw mode turns Ctrl+C into an ordinary key press
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Enter => game.start_game(),
+                KeyCode::Tab => game.normal_mode = !game.normal_mode,
+                KeyCode::Left | KeyCode::Right if !game.in_round() => {
+                    game.test_variant = next_variant(game.test_variant, key.code == KeyCode::Right);
+                }
+                KeyCode::Backspace | KeyCode::Delete => game.delete_last(),
+                KeyCode::Char(c) => game.press_char(c),
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        game.advance((now - last_frame).as_secs_f32());
+        last_frame = now;
+
+        // Only repaint when something changed, to avoid flicker
+        let lines = render(&game);
+        if lines != shown {
+            draw(stdout, &lines)?;
+            shown = lines;
+        }
+    }
+}
+
+fn next_variant(current: TestVariant, forward: bool) -> TestVariant {
+    let variants = TestVariant::ALL;
+    let index = variants.iter().position(|v| *v == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % variants.len()
+    } else {
+        (index + variants.len() - 1) % variants.len()
+    };
+    variants[next]
+}
+
+fn direction_color(direction: &InputDirection) -> Color {
+    match direction {
+        InputDirection::Forward => Color::Rgb { r: 0, g: 150, b: 255 },
+        InputDirection::Reverse => Color::Rgb { r: 255, g: 100, b: 100 },
+        InputDirection::Sequencing => Color::Rgb { r: 120, g: 200, b: 80 },
+    }
+}
+
+fn joined(items: impl Iterator<Item = char>, separator: &str) -> String {
+    items.map(|c| c.to_string()).collect::<Vec<String>>().join(separator)
+}
+
+fn render(game: &MemoryGame) -> Vec<Line> {
+    let mut lines: Vec<Line> = vec![];
+    let mode = if game.normal_mode { "Normal" } else { "Strict" };
+    lines.push((format!("Memory Game  [{}]  [{}]", mode, game.test_variant.short_name()), None));
+    lines.push(("Enter: new game  Tab: Normal/Strict  Left/Right: test  Esc/Ctrl+C: quit".to_string(), None));
+    lines.push((String::new(), None));
+
+    let sequence = game.sequence();
+    match game.phase() {
+        GamePhase::NotStarted => {
+            lines.push(("Press Enter to begin!".to_string(), None));
+            lines.push(("1. Remember the sequence of numbers and letters shown on screen".to_string(), None));
+            lines.push(("2. Enter them in the required order when prompted".to_string(), None));
+            lines.push(("3. Use Backspace/Delete to remove the last entry if you make a mistake".to_string(), None));
+        }
+
+        GamePhase::ShowingSequence => {
+            lines.push(("Remember the sequence:".to_string(), None));
+            let shown = (game.current_sequence_index() + 1).min(sequence.len());
+            lines.push((joined(sequence[..shown].iter().map(|c| c.as_char()), " "), None));
+            lines.push((format!("Showing sequence... {}/{}", shown, sequence.len()), None));
+        }
+
+        GamePhase::Inputting => {
+            let direction = game.input_direction();
+            lines.push(("Enter the sequence:".to_string(), None));
+            lines.push((format!("Direction: {}", direction.short_name()), Some(direction_color(direction))));
+            lines.push((format!("Your input: [{}]", joined(game.user_input().iter().map(|c| c.as_char()), " ")), None));
+
+            // Only show error in Strict mode during input
+            if !game.normal_mode
+                && let Some(error) = game.error_message() {
+                lines.push((error.to_string(), Some(Color::Red)));
+            }
+
+            lines.push((
+                format!("Characters remaining: {}", sequence.len() - game.user_input().len()),
+                None,
+            ));
+        }
+
+        GamePhase::ProbeRecall => {
+            lines.push((format!("What was item #{}?", game.probe_index() + 1), None));
+            lines.push(("Press the key for that item".to_string(), None));
+        }
+
+        GamePhase::Recognition => {
+            lines.push(("Is this the sequence you saw? (Y/N)".to_string(), None));
+            let candidate = &game.recognition_probe().candidate;
+            lines.push((joined(candidate.iter().map(|c| c.as_char()), " "), None));
+        }
+
+        GamePhase::GameOver | GamePhase::Success => {
+            if matches!(game.phase(), GamePhase::Success) {
+                lines.push(("Congratulations!".to_string(), Some(Color::Green)));
+            } else {
+                lines.push(("Game Over!".to_string(), Some(Color::Red)));
+                if let Some(error) = game.error_message() {
+                    lines.push((error.to_string(), Some(Color::Red)));
+                }
+            }
+
+            if game.test_variant == TestVariant::SerialRecall {
+                let direction = game.input_direction();
+                lines.push((format!("Your input: {}", joined(game.user_input().iter().map(|c| c.as_char()), ", ")), None));
+                let correct: Vec<char> = game.expected_input().iter().map(|c| c.as_char()).collect();
+                lines.push((
+                    format!("Correct sequence ({}): {}", direction.short_name(), joined(correct.into_iter(), ", ")),
+                    Some(direction_color(direction)),
+                ));
+            } else {
+                lines.push((format!("The sequence was: {}", joined(sequence.iter().map(|c| c.as_char()), ", ")), None));
+            }
+
+            if let Some(duration) = game.input_duration() {
+                lines.push((format!("Time taken: {:.2} seconds", duration.as_secs_f32()), None));
+            }
+
+            lines.push(("Press Enter to play again".to_string(), None));
+        }
+    }
+
+    lines
+}
+
+fn draw(stdout: &mut io::Stdout, lines: &[Line]) -> io::Result<()> {
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+    for (row, (text, color)) in lines.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16))?;
+        if let Some(color) = color {
+            queue!(stdout, SetForegroundColor(*color), Print(text), ResetColor)?;
+        } else {
+            queue!(stdout, Print(text))?;
+        }
+    }
+    stdout.flush()
+}
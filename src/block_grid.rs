@@ -0,0 +1,45 @@
+use rand::Rng;
+
+// Number of blocks on the board, as in the classic Corsi test
+pub const BLOCK_COUNT: usize = 9;
+
+// Blocks are placed on distinct cells of a coarse layout grid
+pub const GRID_COLUMNS: usize = 5;
+pub const GRID_ROWS: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct BlockGrid {
+    // Block centres in normalised board coordinates (0.0..=1.0)
+    positions: Vec<(f32, f32)>,
+}
+
+impl BlockGrid {
+    pub fn random<R: Rng>(rng: &mut R, count: usize) -> Self {
+        let count = count.min(GRID_COLUMNS * GRID_ROWS);
+        let positions = rand::seq::index::sample(rng, GRID_COLUMNS * GRID_ROWS, count)
+            .iter()
+            .map(|cell| {
+                let column = cell % GRID_COLUMNS;
+                let row = cell / GRID_COLUMNS;
+                (
+                    (column as f32 + 0.5) / GRID_COLUMNS as f32,
+                    (row as f32 + 0.5) / GRID_ROWS as f32,
+                )
+            })
+            .collect();
+
+        Self { positions }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn positions(&self) -> &[(f32, f32)] {
+        &self.positions
+    }
+}
@@ -0,0 +1,420 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::alphabet::Alphabet;
+use crate::char_type::CharType;
+use crate::direction_policy::DirectionPolicy;
+use crate::game_mode::GameMode;
+use crate::memory_game::MemoryGame;
+use crate::recall_test::TestVariant;
+use crate::round_result::{format_date, unix_timestamp, RoundResult};
+use crate::settings::Settings;
+
+// Modes the daily challenge picks from: the ones with a length and a direction
+const DAILY_MODES: [GameMode; 3] = [GameMode::Sequence, GameMode::SpatialSpan, GameMode::ComplexSpan];
+
+// Challenge codes are bytes in Crockford's base32, which avoids letters easily mistaken for digits
+const CODE_VERSION: u8 = 1;
+const CODE_DIGITS: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_GROUP: usize = 5; // Characters between dashes
+
+// A round everyone who plays it gets identically
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub seed: u64,
+    pub game_mode: GameMode,
+    pub test_variant: TestVariant,
+    pub sequence_length: usize,
+    pub alphabet: Alphabet,
+    pub direction_policy: DirectionPolicy,
+    pub item_display_ms: u64,
+    pub strict: bool,
+    pub daily: Option<u64>, // Day (since the Unix epoch) of a daily challenge
+}
+
+impl Challenge {
+    // Everything follows from the date, so the whole team plays the same round that day
+    pub fn daily(day: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(day);
+        let game_mode = DAILY_MODES[rng.gen_range(0..DAILY_MODES.len())];
+        let direction_policy = if rng.gen_bool(0.5) {
+            DirectionPolicy::Forward
+        } else {
+            DirectionPolicy::Reverse
+        };
+
+        Self {
+            seed: rng.r#gen(),
+            game_mode,
+            test_variant: TestVariant::SerialRecall,
+            sequence_length: rng.gen_range(4..=8),
+            alphabet: Alphabet::Mixed,
+            direction_policy,
+            item_display_ms: 800,
+            strict: false,
+            daily: Some(day),
+        }
+    }
+
+    // Days are counted in UTC, so the challenge changes at the same moment for everyone
+    pub fn today() -> Self {
+        Challenge::daily(unix_timestamp() / 86_400)
+    }
+
+    // A new round with the game's current settings
+    pub fn from_settings(game: &MemoryGame, seed: u64) -> Result<Self, String> {
+        if game.game_mode.is_continuous() {
+            return Err("N-back rounds can't be shared as challenges".to_string());
+        }
+
+        Ok(Self {
+            seed,
+            game_mode: game.game_mode,
+            test_variant: game.test_variant,
+            sequence_length: game.sequence_length,
+            alphabet: game.alphabet.clone(),
+            direction_policy: game.direction_policy,
+            item_display_ms: (game.item_display_secs * 1000.0).round() as u64,
+            strict: !game.normal_mode,
+            daily: None,
+        })
+    }
+
+    // The round the game just played, for someone else to play exactly
+    pub fn from_round(game: &MemoryGame) -> Result<Self, String> {
+        if game.game_mode.is_continuous() {
+            return Err("N-back rounds can't be shared as challenges".to_string());
+        }
+
+        Ok(Self {
+            seed: game.round_seed,
+            game_mode: game.game_mode,
+            test_variant: game.test_variant,
+            sequence_length: game.sequence.len(),
+            alphabet: game.alphabet.clone(),
+            // The direction that was played, as alternating depends on the round number
+            direction_policy: DirectionPolicy::fixed(&game.input_direction),
+            item_display_ms: (game.item_display_secs * 1000.0).round() as u64,
+            strict: !game.normal_mode,
+            daily: None,
+        })
+    }
+
+    // Compact text such as "0G0KW-...", with a checksum so typos are caught. Lengths and display
+    // times the code has no room for are an error, as the round would come out different.
+    pub fn to_code(&self) -> Result<String, String> {
+        let sequence_length = u8::try_from(self.sequence_length)
+            .map_err(|_| format!("rounds of more than {} items can't be shared as challenges", u8::MAX))?;
+        let item_display_ms = u16::try_from(self.item_display_ms)
+            .map_err(|_| format!("display times over {} ms can't be shared as challenges", u16::MAX))?;
+
+        let mut bytes = vec![
+            CODE_VERSION,
+            (index_of(&GameMode::ALL, &self.game_mode) << 4) | index_of(&TestVariant::ALL, &self.test_variant),
+            sequence_length,
+            index_of(&DirectionPolicy::ALL, &self.direction_policy) | if self.strict { 0x80 } else { 0 },
+        ];
+        bytes.extend_from_slice(&item_display_ms.to_be_bytes());
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        match &self.alphabet {
+            Alphabet::Mixed => bytes.push(0),
+            Alphabet::Digits => bytes.push(1),
+            Alphabet::Letters => bytes.push(2),
+            Alphabet::Custom(items) => {
+                bytes.push(3);
+                bytes.push(items.len() as u8);
+                bytes.extend(items.iter().map(|item| item.as_char() as u8));
+            }
+        }
+        bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+
+        let text = encode_base32(&bytes);
+        Ok(text
+            .as_bytes()
+            .chunks(CODE_GROUP)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect::<Vec<_>>()
+            .join("-"))
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        let bytes = decode_base32(code)?;
+        if bytes.len() < 17 {
+            return Err("the code is too short".to_string());
+        }
+
+        let (payload, sum) = bytes.split_at(bytes.len() - 2);
+        if checksum(payload).to_be_bytes() != sum {
+            return Err("the code has a typo (checksum doesn't match)".to_string());
+        }
+        if payload[0] != CODE_VERSION {
+            return Err(format!("the code is from an unknown version ({})", payload[0]));
+        }
+
+        let game_mode = *GameMode::ALL
+            .get((payload[1] >> 4) as usize)
+            .ok_or("the code has an unknown game type")?;
+        if game_mode.is_continuous() {
+            return Err("N-back rounds can't be shared as challenges".to_string());
+        }
+        let test_variant = *TestVariant::ALL
+            .get((payload[1] & 0x0f) as usize)
+            .ok_or("the code has an unknown test variant")?;
+        let sequence_length = payload[2] as usize;
+        if sequence_length == 0 {
+            return Err("the code has an empty sequence".to_string());
+        }
+        let direction_policy = *DirectionPolicy::ALL
+            .get((payload[3] & 0x7f) as usize)
+            .ok_or("the code has unknown direction rules")?;
+        let item_display_ms = u16::from_be_bytes([payload[4], payload[5]]) as u64;
+        if item_display_ms == 0 {
+            return Err("the code has no display time".to_string());
+        }
+        let seed = u64::from_be_bytes(payload[6..14].try_into().unwrap());
+
+        let alphabet = match (payload[14], &payload[15..]) {
+            (0, []) => Alphabet::Mixed,
+            (1, []) => Alphabet::Digits,
+            (2, []) => Alphabet::Letters,
+            (3, [count, items @ ..]) if *count as usize == items.len() => {
+                let text: String = items.iter().map(|item| *item as char).collect();
+                if !text.chars().all(|c| CharType::from_char(c).is_some()) {
+                    return Err("the code has characters the game can't use".to_string());
+                }
+                Alphabet::parse(&text)?
+            }
+            _ => return Err("the code has an unknown alphabet".to_string()),
+        };
+
+        Ok(Self {
+            seed,
+            game_mode,
+            test_variant,
+            sequence_length,
+            alphabet,
+            direction_policy,
+            item_display_ms,
+            strict: payload[3] & 0x80 != 0,
+            daily: None,
+        })
+    }
+}
+
+fn index_of<T: PartialEq>(all: &[T], value: &T) -> u8 {
+    all.iter().position(|other| other == value).unwrap_or(0) as u8
+}
+
+// Fletcher-16, enough to catch a mistyped or swapped character
+fn checksum(bytes: &[u8]) -> u16 {
+    let (mut low, mut high) = (0u16, 0u16);
+    for byte in bytes {
+        low = (low + *byte as u16) % 255;
+        high = (high + low) % 255;
+    }
+    (high << 8) | low
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            text.push(CODE_DIGITS[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        text.push(CODE_DIGITS[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    text
+}
+
+// Dashes, spaces and case don't matter; O, I and L are read as 0, 1 and 1
+fn decode_base32(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '-') {
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let value = CODE_DIGITS
+            .iter()
+            .position(|digit| *digit as char == c)
+            .ok_or_else(|| format!("'{}' can't appear in a challenge code", c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    // Left over bits are padding from encoding, so anything else there is a typo
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return Err("the code has a typo (it doesn't end where it should)".to_string());
+    }
+    Ok(bytes)
+}
+
+// Calendar date of a daily challenge day
+pub fn daily_date(day: u64) -> String {
+    format_date(day * 86_400)
+}
+
+// Wordle-style summary to paste into chat, without giving the items away
+pub fn share_text(result: &RoundResult) -> String {
+    let expected: Vec<char> = result.sequence.chars().collect();
+    let entered: Vec<char> = result.input.chars().collect();
+    let expected = result.direction.arrange(&expected, |c| *c);
+    let marks: String = (0..expected.len())
+        .map(|i| match entered.get(i) {
+            Some(c) if *c == expected[i] => '🟩',
+            Some(_) => '🟥',
+            None => '⬛',
+        })
+        .collect();
+
+    let title = match &result.daily {
+        Some(date) => format!("Memory Game Daily {}", date),
+        None => "Memory Game".to_string(),
+    };
+    let time = result
+        .input_duration_ms
+        .map_or("-".to_string(), |millis| format!("{:.2}s", millis as f32 / 1000.0));
+
+    format!(
+        "{}\n{} {} {}\n{}\n⏱ {} ⭐ {}",
+        title,
+        result.game_mode.short_name(),
+        result.length(),
+        result.direction.short_name(),
+        marks,
+        time,
+        result.score
+    )
+}
+
+impl MemoryGame {
+    // Plays the challenge round with its own settings; the player's come back with end_challenge
+    pub fn start_challenge(&mut self, challenge: Challenge) {
+        if self.settings_before_challenge.is_none() {
+            self.settings_before_challenge = Some((Settings::from_game(self), self.test_variant));
+        }
+
+        self.game_mode = challenge.game_mode;
+        self.test_variant = challenge.test_variant;
+        self.sequence_length = challenge.sequence_length;
+        self.alphabet = challenge.alphabet.clone();
+        self.direction_policy = challenge.direction_policy;
+        self.item_display_secs = challenge.item_display_ms as f32 / 1000.0;
+        self.normal_mode = !challenge.strict;
+
+        // The attempt counts from the moment the round starts, so leaving it doesn't give a retry
+        if let Some(day) = challenge.daily {
+            let date = daily_date(day);
+            self.daily_counts = !self.daily_attempts.contains(&date);
+            if self.daily_counts {
+                if let (Some(store), Some(name)) = (&self.profiles, &self.profile)
+                    && let Err(e) = store.record_daily_attempt(name, &date) {
+                    self.warning = Some(format!("Could not save the daily challenge attempt: {}", e));
+                }
+                self.daily_attempts.push(date);
+            }
+        }
+
+        let seed = challenge.seed;
+        self.challenge = Some(challenge);
+        self.start_round(seed);
+    }
+
+    pub fn end_challenge(&mut self) {
+        if let Some((settings, test_variant)) = self.settings_before_challenge.take() {
+            settings.apply(self);
+            self.test_variant = test_variant;
+        }
+        self.challenge = None;
+    }
+
+    pub fn challenge(&self) -> Option<&Challenge> {
+        self.challenge.as_ref()
+    }
+
+    // Whether the daily challenge being played is the player's first attempt at it
+    pub fn daily_attempt_counts(&self) -> bool {
+        self.daily_counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            seed: 0x0123_4567_89ab_cdef,
+            game_mode: GameMode::ComplexSpan,
+            test_variant: TestVariant::SerialRecall,
+            sequence_length: 7,
+            alphabet: Alphabet::Mixed,
+            direction_policy: DirectionPolicy::Reverse,
+            item_display_ms: 650,
+            strict: true,
+            daily: None,
+        }
+    }
+
+    #[test]
+    fn codes_give_back_the_same_round() {
+        let custom = Challenge {
+            game_mode: GameMode::Sequence,
+            test_variant: TestVariant::Recognition,
+            alphabet: Alphabet::parse("AB12").unwrap(),
+            strict: false,
+            ..challenge()
+        };
+        let longest = Challenge { sequence_length: 255, item_display_ms: 65_535, ..challenge() };
+
+        for challenge in [challenge(), custom, longest] {
+            let code = challenge.to_code().unwrap();
+            assert_eq!(Challenge::from_code(&code), Ok(challenge.clone()));
+            // Case and dashes don't matter
+            assert_eq!(Challenge::from_code(&code.replace('-', "").to_lowercase()), Ok(challenge));
+        }
+    }
+
+    #[test]
+    fn rounds_the_code_has_no_room_for_are_an_error() {
+        assert!(Challenge { sequence_length: 256, ..challenge() }.to_code().is_err());
+        assert!(Challenge { item_display_ms: 65_536, ..challenge() }.to_code().is_err());
+    }
+
+    #[test]
+    fn mistyped_codes_are_caught_by_the_checksum() {
+        let code = challenge().to_code().unwrap();
+        let digits: Vec<char> = code.chars().collect();
+
+        // Every other digit in one place, away from the padding at the end
+        for position in [0, 7, 14, 21] {
+            for digit in CODE_DIGITS.iter().map(|digit| *digit as char).filter(|digit| *digit != digits[position]) {
+                let mut typo = digits.clone();
+                typo[position] = digit;
+                let typo: String = typo.into_iter().collect();
+                assert!(Challenge::from_code(&typo).unwrap_err().contains("typo"), "{} was read", typo);
+            }
+        }
+
+        // Two neighbours swapped
+        let mut swapped = digits.clone();
+        let position = (0..digits.len() - 1)
+            .find(|i| digits[*i] != digits[*i + 1] && !digits[*i..*i + 2].contains(&'-'))
+            .unwrap();
+        swapped.swap(position, position + 1);
+        let swapped: String = swapped.into_iter().collect();
+        assert!(Challenge::from_code(&swapped).unwrap_err().contains("typo"));
+    }
+}
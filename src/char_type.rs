@@ -1,14 +1,37 @@
-#[derive(Debug, Clone, PartialEq)]
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CharType {
     Number(u8),
     Letter(u8),
+    Block(u8), // Index of a block in the spatial span grid
 }
 
 impl CharType {
+    // Either a number (0-9) or a letter (A-Z), with equal odds
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        if rng.gen_bool(0.5) {
+            CharType::Number(rng.gen_range(b'0'..=b'9'))
+        } else {
+            CharType::Letter(rng.gen_range(b'A'..=b'Z'))
+        }
+    }
+
+    // The typed item for a character, if it is one the game uses
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '0'..='9' => Some(CharType::Number(c as u8)),
+            'A'..='Z' => Some(CharType::Letter(c as u8)),
+            _ => None,
+        }
+    }
+
     pub fn as_char(&self) -> char {
         match self {
             CharType::Number(n) => *n as char,
             CharType::Letter(l) => *l as char,
+            CharType::Block(b) => (b'1' + *b) as char, // Blocks are labelled from 1
         }
     }
 }
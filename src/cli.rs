@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+
+use crate::alphabet::Alphabet;
+use crate::digit_span::DigitSpanProtocol;
+use crate::direction_policy::DirectionPolicy;
+use crate::markers::MarkerCodes;
+use crate::memory_game::MemoryGame;
+use crate::norms::Norms;
+use crate::profile::ProfileStore;
+use crate::quest::{QuestParameter, QuestSetup};
+use crate::race::RaceRole;
+use crate::session::load_consent;
+use crate::study::StudyDesign;
+
+pub const USAGE: &str = "\
+Usage: memory_game [OPTIONS]
+
+Options:
+  --length N             Sequence length (default 5)
+  --seed N               Seed for a reproducible session
+  --strict               Start in Strict mode instead of Normal
+  --normal               Start in Normal mode, even if the settings file says Strict
+  --direction POLICY     random, forward, reverse, alternate or sequencing (default random)
+  --item-ms N            How long each item is shown, in milliseconds (default 800)
+  --alphabet SET         mixed, digits, letters or the characters to use, e.g. ABC123
+  --window WxH           Window size in pixels (default 600x500)
+  --fullscreen           Start fullscreen
+  --profile NAME         Play as this profile, creating it if needed
+  --output FILE          Append finished rounds to FILE as JSON lines
+  --server HOST:PORT     Submit rounds to a leaderboard_server and show its rankings
+  --host-race ADDR       Wait for an opponent to race on ADDR, e.g. 0.0.0.0:7880
+  --join-race HOST:PORT  Race against the game hosting on HOST:PORT
+  --broadcast ADDR       Let viewers watch the rounds played here, e.g. 0.0.0.0:7890
+  --watch HOST:PORT      Only watch the game broadcasting on HOST:PORT
+  --event-feed ADDR      Publish round events as JSON over WebSocket, e.g. 127.0.0.1:7891
+  --markers HOST:PORT    Send numbered UDP markers for item onsets, keystrokes etc.
+  --marker-codes FILE    TOML table of the codes sent with each kind of marker
+  --experimenter         Open the experimenter console; this window only shows the trials
+  --session              Ask for participant ID, session number etc. before the first round
+  --consent FILE         Text the participant agrees to before starting (implies --session)
+  --study FILE           Run the blocks of a study design, ordered by participant number
+                         (implies --session)
+  --digit-span FILE      Start the Forward/Backward/Sequencing digit span battery with the
+                         protocol in FILE (TOML; see the README)
+  --threshold PARAMETER  Estimate the length or speed (display time) threshold adaptively
+  --threshold-target N   Success rate in percent the threshold is for (default 75)
+  --norms FILE           CSV of norms to compare battery scores with (default norms.csv in
+                         the config directory, if there is one)
+  -h, --help             Show this help
+
+Profile commands (run without opening the window):
+  --list-profiles
+  --create-profile NAME
+  --rename-profile OLD NEW
+  --delete-profile NAME";
+
+#[derive(Debug, Clone)]
+pub enum ProfileCommand {
+    List,
+    Create(String),
+    Rename(String, String),
+    Delete(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CliOptions {
+    pub sequence_length: Option<usize>,
+    pub seed: Option<u64>,
+    pub strict: Option<bool>, // --strict or --normal, whichever came last
+    pub direction_policy: Option<DirectionPolicy>,
+    pub item_display_ms: Option<u64>,
+    pub alphabet: Option<Alphabet>,
+    pub window_size: Option<[f32; 2]>,
+    pub fullscreen: bool,
+    pub profile: Option<String>,
+    pub output: Option<PathBuf>,
+    pub server: Option<String>,
+    pub race: Option<(RaceRole, String)>,
+    pub broadcast: Option<String>,
+    pub watch: Option<String>,
+    pub event_feed: Option<String>,
+    pub markers: Option<String>,
+    pub marker_codes: Option<MarkerCodes>,
+    pub experimenter: bool,
+    pub session: bool,
+    pub consent: Option<String>,
+    pub study: Option<StudyDesign>,
+    pub digit_span: Option<DigitSpanProtocol>,
+    pub norms: Option<PathBuf>,
+    pub threshold: Option<QuestParameter>,
+    pub threshold_target: Option<f64>,
+    pub profile_command: Option<ProfileCommand>,
+    pub help: bool,
+}
+
+impl CliOptions {
+    // Parses the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+            match arg.as_str() {
+                "--length" => {
+                    let length = parse_number(&value("--length")?, "--length")?;
+                    if length == 0 {
+                        return Err("--length must be at least 1".to_string());
+                    }
+                    options.sequence_length = Some(length as usize);
+                }
+                "--seed" => options.seed = Some(parse_number(&value("--seed")?, "--seed")?),
+                "--strict" => options.strict = Some(true),
+                "--normal" => options.strict = Some(false),
+                "--direction" => options.direction_policy = Some(DirectionPolicy::parse(&value("--direction")?)?),
+                "--item-ms" => {
+                    let millis = parse_number(&value("--item-ms")?, "--item-ms")?;
+                    if millis == 0 {
+                        return Err("--item-ms must be at least 1".to_string());
+                    }
+                    options.item_display_ms = Some(millis);
+                }
+                "--alphabet" => options.alphabet = Some(Alphabet::parse(&value("--alphabet")?)?),
+                "--window" => options.window_size = Some(parse_window_size(&value("--window")?)?),
+                "--fullscreen" => options.fullscreen = true,
+                "--profile" => options.profile = Some(value("--profile")?),
+                "--output" => options.output = Some(PathBuf::from(value("--output")?)),
+                "--server" => options.server = Some(value("--server")?),
+                "--host-race" => options.race = Some((RaceRole::Host, value("--host-race")?)),
+                "--join-race" => options.race = Some((RaceRole::Joiner, value("--join-race")?)),
+                "--broadcast" => options.broadcast = Some(value("--broadcast")?),
+                "--watch" => options.watch = Some(value("--watch")?),
+                "--event-feed" => options.event_feed = Some(value("--event-feed")?),
+                "--markers" => options.markers = Some(value("--markers")?),
+                "--experimenter" => options.experimenter = true,
+                "--session" => options.session = true,
+                "--consent" => options.consent = Some(load_consent(Path::new(&value("--consent")?))?),
+                "--digit-span" => options.digit_span = Some(DigitSpanProtocol::load(Path::new(&value("--digit-span")?))?),
+                "--threshold" => options.threshold = Some(QuestParameter::parse(&value("--threshold")?)?),
+                "--threshold-target" => {
+                    let percent = parse_number(&value("--threshold-target")?, "--threshold-target")?;
+                    if !(5..=95).contains(&percent) {
+                        return Err("--threshold-target must be between 5 and 95".to_string());
+                    }
+                    options.threshold_target = Some(percent as f64 / 100.0);
+                }
+                "--norms" => options.norms = Some(PathBuf::from(value("--norms")?)),
+                "--study" => options.study = Some(StudyDesign::load(Path::new(&value("--study")?))?),
+                "--marker-codes" => options.marker_codes = Some(MarkerCodes::load(Path::new(&value("--marker-codes")?))?),
+                "--list-profiles" => options.profile_command = Some(ProfileCommand::List),
+                "--create-profile" => {
+                    options.profile_command = Some(ProfileCommand::Create(value("--create-profile")?));
+                }
+                "--rename-profile" => {
+                    let old = value("--rename-profile")?;
+                    let new = value("--rename-profile")?;
+                    options.profile_command = Some(ProfileCommand::Rename(old, new));
+                }
+                "--delete-profile" => {
+                    options.profile_command = Some(ProfileCommand::Delete(value("--delete-profile")?));
+                }
+                "-h" | "--help" => options.help = true,
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    // Runs a profile command, returning what to print
+    pub fn run_profile_command(command: &ProfileCommand, store: &ProfileStore) -> Result<String, String> {
+        match command {
+            ProfileCommand::List => Ok(store.list().join("\n")),
+            ProfileCommand::Create(name) => store.create(name).map(|_| format!("Created profile '{}'", name)),
+            ProfileCommand::Rename(old, new) => store
+                .rename(old, new)
+                .map(|_| format!("Renamed profile '{}' to '{}'", old, new)),
+            ProfileCommand::Delete(name) => store.delete(name).map(|_| format!("Deleted profile '{}'", name)),
+        }
+    }
+
+    // Game settings given on the command line override the settings file for this session only
+    pub fn apply(&self, game: &mut MemoryGame) {
+        if let Some(length) = self.sequence_length {
+            game.sequence_length = length;
+        }
+        if let Some(seed) = self.seed {
+            game.set_seed(seed);
+        }
+        if let Some(strict) = self.strict {
+            game.normal_mode = !strict;
+        }
+        if let Some(policy) = self.direction_policy {
+            game.direction_policy = policy;
+        }
+        if let Some(millis) = self.item_display_ms {
+            game.item_display_secs = millis as f32 / 1000.0;
+        }
+        if let Some(alphabet) = &self.alphabet {
+            game.alphabet = alphabet.clone();
+        }
+        if let Some(output) = &self.output {
+            game.results_path = Some(output.clone());
+        }
+        if let Some(server) = &self.server {
+            game.leaderboard_server = Some(server.clone());
+        }
+        match &self.race {
+            Some((RaceRole::Host, address)) => {
+                if let Err(e) = game.host_race(address) {
+                    game.warning = Some(format!("Race: {}", e));
+                }
+            }
+            Some((RaceRole::Joiner, address)) => game.join_race(address),
+            None => {}
+        }
+        if let Some(address) = &self.broadcast
+            && let Err(e) = game.start_broadcast(address) {
+            game.warning = Some(format!("Broadcast: {}", e));
+        }
+        if let Some(address) = &self.event_feed
+            && let Err(e) = game.start_event_feed(address) {
+            game.warning = Some(format!("Event feed: {}", e));
+        }
+        if let Some(target) = &self.markers
+            && let Err(e) = game.start_markers(target, self.marker_codes.clone().unwrap_or_default()) {
+            game.warning = Some(format!("Markers: {}", e));
+        }
+        if self.experimenter {
+            game.open_experimenter_console();
+        }
+        // Norms are optional, so only a file asked for by name has to be there
+        if let Some(path) = self.norms.clone().or_else(Norms::path) {
+            match Norms::load(&path) {
+                Ok(Some((norms, warnings))) => {
+                    if let Some(first) = warnings.first() {
+                        game.warning = Some(format!("Norms: {} ({} rows left out in all)", first, warnings.len()));
+                    }
+                    game.set_norms(Some(norms));
+                }
+                Ok(None) if self.norms.is_some() => game.warning = Some(format!("Norms: {} not found", path.display())),
+                Ok(None) => {}
+                Err(e) => game.warning = Some(format!("Norms: {}", e)),
+            }
+        }
+        if let Some(parameter) = self.threshold
+            && let Err(e) = game.start_quest(parameter, self.threshold_target.unwrap_or(QuestSetup::default().target)) {
+            game.warning = Some(format!("Threshold: {}", e));
+        }
+        if let Some(protocol) = &self.digit_span
+            && let Err(e) = game.start_digit_span(protocol.clone()) {
+            game.warning = Some(format!("Digit span: {}", e));
+        }
+        if self.session || self.consent.is_some() || self.study.is_some() {
+            game.set_consent(self.consent.clone());
+            game.set_study_design(self.study.clone());
+            game.open_session_setup();
+        }
+        if let Some(address) = &self.watch {
+            game.watch(address);
+            game.show_profiles = false;
+        }
+    }
+}
+
+fn parse_number(text: &str, name: &str) -> Result<u64, String> {
+    text.parse()
+        .map_err(|_| format!("{} expects a whole number, got '{}'", name, text))
+}
+
+fn parse_window_size(text: &str) -> Result<[f32; 2], String> {
+    let (width, height) = text
+        .split_once('x')
+        .ok_or_else(|| format!("--window expects WIDTHxHEIGHT, got '{}'", text))?;
+    let width = parse_number(width, "--window")? as f32;
+    let height = parse_number(height, "--window")? as f32;
+    Ok([width, height])
+}
@@ -0,0 +1,112 @@
+use rand::Rng;
+use std::ops::RangeInclusive;
+
+// Equation accuracy thresholds the settings offer
+pub const ACCURACY_THRESHOLDS: RangeInclusive<f64> = 0.5..=1.0;
+
+#[derive(Debug, Clone)]
+pub struct Equation {
+    left: u8,
+    operator: char,
+    right: u8,
+    shown_result: i32,
+    is_true: bool,
+}
+
+impl Equation {
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let left = rng.gen_range(1..=9);
+        let right = rng.gen_range(1..=9);
+        let operator = ['+', '-', '×'][rng.gen_range(0..3)];
+        let result = match operator {
+            '+' => left as i32 + right as i32,
+            '-' => left as i32 - right as i32,
+            _ => left as i32 * right as i32,
+        };
+
+        // False equations are off by a small amount so they can't be rejected at a glance
+        let is_true = rng.gen_bool(0.5);
+        let shown_result = if is_true {
+            result
+        } else if rng.gen_bool(0.5) {
+            result + rng.gen_range(1..=3)
+        } else {
+            result - rng.gen_range(1..=3)
+        };
+
+        Self { left, operator, right, shown_result, is_true }
+    }
+
+    pub fn text(&self) -> String {
+        format!("{} {} {} = {}", self.left, self.operator, self.right, self.shown_result)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplexSpan {
+    pub accuracy_threshold: f64, // Minimum share of equations verified correctly for a round to count
+    equations: Vec<Equation>,
+    answers: Vec<Option<bool>>,
+}
+
+impl ComplexSpan {
+    pub fn new() -> Self {
+        Self {
+            accuracy_threshold: 0.85,
+            equations: vec![],
+            answers: vec![],
+        }
+    }
+
+    // One equation between each pair of consecutive items
+    pub fn generate_equations<R: Rng>(&mut self, rng: &mut R, item_count: usize) {
+        let count = item_count.saturating_sub(1);
+        self.equations = (0..count).map(|_| Equation::random(rng)).collect();
+        self.answers = vec![None; count];
+    }
+
+    // The equation that has to be answered before item `index` can be shown
+    pub fn pending_equation(&self, index: usize) -> Option<&Equation> {
+        let equation_index = index.checked_sub(1)?;
+        match self.answers.get(equation_index) {
+            Some(None) => self.equations.get(equation_index),
+            _ => None,
+        }
+    }
+
+    pub fn answer(&mut self, index: usize, answer: bool) {
+        if let Some(equation_index) = index.checked_sub(1)
+            && let Some(slot @ None) = self.answers.get_mut(equation_index) {
+            *slot = Some(answer);
+        }
+    }
+
+    pub fn correct_answers(&self) -> usize {
+        self.equations
+            .iter()
+            .zip(&self.answers)
+            .filter(|(equation, answer)| **answer == Some(equation.is_true))
+            .count()
+    }
+
+    pub fn equation_count(&self) -> usize {
+        self.equations.len()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.equations.is_empty() {
+            return 1.0;
+        }
+        self.correct_answers() as f64 / self.equations.len() as f64
+    }
+
+    pub fn meets_threshold(&self) -> bool {
+        self.accuracy() >= self.accuracy_threshold
+    }
+}
+
+impl Default for ComplexSpan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
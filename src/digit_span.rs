@@ -0,0 +1,317 @@
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::alphabet::Alphabet;
+use crate::direction_policy::DirectionPolicy;
+use crate::game_mode::GameMode;
+use crate::input_direction::InputDirection;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::recall_test::TestVariant;
+use crate::round_result::RoundResult;
+use crate::settings::Settings;
+
+// Clinical-style digit span: Forward, Backward and Sequencing in turn, a fixed number of trials
+// at each length, going up a length until every trial at one length fails. Each direction is
+// scored by its longest span and its raw total (trials correct).
+
+// The protocol, from digit_span.toml in the config dir or --digit-span FILE, e.g.
+//
+//   directions = ["forward", "backward", "sequencing"]
+//   start_length = 2
+//   max_length = 9
+//   trials_per_length = 2
+//   item_ms = 1000
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DigitSpanProtocol {
+    pub directions: Vec<String>,
+    pub start_length: usize,
+    pub max_length: usize,
+    pub trials_per_length: u32,
+    pub item_ms: u64,
+}
+
+impl Default for DigitSpanProtocol {
+    fn default() -> Self {
+        Self {
+            directions: vec!["forward".to_string(), "backward".to_string(), "sequencing".to_string()],
+            start_length: 2,
+            max_length: 9,
+            trials_per_length: 2,
+            item_ms: 1000,
+        }
+    }
+}
+
+impl DigitSpanProtocol {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("memory_game").join("digit_span.toml"))
+    }
+
+    // A missing file gives the standard protocol
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let protocol: DigitSpanProtocol = match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => DigitSpanProtocol::default(),
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        protocol.directions().map_err(|e| format!("{}: {}", path.display(), e))?;
+        if protocol.start_length == 0 || protocol.max_length < protocol.start_length {
+            return Err(format!("{}: lengths must run from at least 1 up to max_length", path.display()));
+        }
+        if protocol.trials_per_length == 0 || protocol.item_ms == 0 {
+            return Err(format!("{}: trials_per_length and item_ms must be at least 1", path.display()));
+        }
+        Ok(protocol)
+    }
+
+    pub fn directions(&self) -> Result<Vec<InputDirection>, String> {
+        if self.directions.is_empty() {
+            return Err("the battery needs at least one direction".to_string());
+        }
+        self.directions.iter().map(|direction| InputDirection::parse(direction)).collect()
+    }
+}
+
+// How one direction went
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionScore {
+    pub direction: InputDirection,
+    pub longest_span: usize, // 0 when no trial was correct
+    pub raw_total: u32,
+    pub trials: Vec<(usize, bool)>, // Length and success of every trial, in order
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigitSpanStage {
+    Instructions, // Before each direction
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigitSpan {
+    pub protocol: DigitSpanProtocol,
+    pub directions: Vec<InputDirection>,
+    pub scores: Vec<DirectionScore>, // One per direction started so far, the last is the current
+    pub length: usize,
+    pub trial: u32,    // At the current length, from 0
+    pub failures: u32, // At the current length
+    pub stage: DigitSpanStage,
+    settings_before: (Settings, TestVariant),
+}
+
+impl DigitSpan {
+    pub fn direction(&self) -> Option<&InputDirection> {
+        self.directions.get(self.scores.len().saturating_sub(1))
+    }
+
+    pub fn instructions(direction: &InputDirection) -> &'static str {
+        match direction {
+            InputDirection::Forward => "Repeat the digits in the same order.",
+            InputDirection::Reverse => "Repeat the digits backwards, starting with the last one.",
+            InputDirection::Sequencing => "Repeat the digits in order, starting with the smallest.",
+        }
+    }
+
+    fn record(&mut self, result: &RoundResult) {
+        let Some(score) = self.scores.last_mut() else {
+            return;
+        };
+        score.trials.push((self.length, result.success));
+        if result.success {
+            score.raw_total += 1;
+            score.longest_span = score.longest_span.max(self.length);
+        } else {
+            self.failures += 1;
+        }
+
+        self.trial += 1;
+        if self.trial < self.protocol.trials_per_length {
+            return;
+        }
+
+        // Discontinued once every trial at a length has failed
+        let discontinue = self.failures == self.protocol.trials_per_length || self.length >= self.protocol.max_length;
+        self.trial = 0;
+        self.failures = 0;
+        self.length += 1;
+        if discontinue {
+            self.stage = if self.scores.len() < self.directions.len() {
+                DigitSpanStage::Instructions
+            } else {
+                DigitSpanStage::Finished
+            };
+        }
+    }
+}
+
+impl MemoryGame {
+    pub fn start_digit_span(&mut self, protocol: DigitSpanProtocol) -> Result<(), String> {
+        let directions = protocol.directions()?;
+        self.end_challenge();
+        self.digit_span = Some(DigitSpan {
+            length: protocol.start_length,
+            protocol,
+            directions,
+            scores: vec![],
+            trial: 0,
+            failures: 0,
+            stage: DigitSpanStage::Instructions,
+            settings_before: (Settings::from_game(self), self.test_variant),
+        });
+        self.phase = GamePhase::NotStarted;
+        Ok(())
+    }
+
+    pub fn digit_span(&self) -> Option<&DigitSpan> {
+        self.digit_span.as_ref()
+    }
+
+    // Counts the finished trial and moves through lengths and directions
+    pub fn record_digit_span_trial(&mut self, result: &RoundResult) {
+        if let Some(digit_span) = &mut self.digit_span
+            && digit_span.stage == DigitSpanStage::Running {
+            digit_span.record(result);
+        }
+    }
+
+    // Starts the next trial, or the next direction after its instructions
+    pub fn next_digit_span_trial(&mut self) {
+        let Some(digit_span) = &mut self.digit_span else {
+            return;
+        };
+        match digit_span.stage {
+            DigitSpanStage::Finished => return,
+            DigitSpanStage::Instructions => {
+                let Some(direction) = digit_span.directions.get(digit_span.scores.len()).cloned() else {
+                    return;
+                };
+                digit_span.scores.push(DirectionScore {
+                    direction,
+                    longest_span: 0,
+                    raw_total: 0,
+                    trials: vec![],
+                });
+                digit_span.length = digit_span.protocol.start_length;
+                digit_span.stage = DigitSpanStage::Running;
+            }
+            DigitSpanStage::Running => {}
+        }
+
+        let Some(direction) = digit_span.direction().cloned() else {
+            return;
+        };
+        self.game_mode = GameMode::Sequence;
+        self.test_variant = TestVariant::SerialRecall;
+        self.alphabet = Alphabet::Digits;
+        self.normal_mode = true;
+        self.direction_policy = DirectionPolicy::fixed(&direction);
+        self.sequence_length = digit_span.length;
+        self.item_display_secs = digit_span.protocol.item_ms as f32 / 1000.0;
+        let seed = self.next_seed();
+        self.start_round(seed);
+    }
+
+    // Back to the settings from before the battery
+    pub fn leave_digit_span(&mut self) {
+        if let Some(digit_span) = self.digit_span.take() {
+            let (settings, variant) = digit_span.settings_before;
+            settings.apply(self);
+            self.test_variant = variant;
+        }
+        self.phase = GamePhase::NotStarted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol(directions: &[&str]) -> DigitSpanProtocol {
+        DigitSpanProtocol {
+            directions: directions.iter().map(|direction| direction.to_string()).collect(),
+            start_length: 2,
+            max_length: 9,
+            trials_per_length: 2,
+            item_ms: 1000,
+        }
+    }
+
+    // Plays the trial the battery is on and books it as correct or not
+    fn trial(game: &mut MemoryGame, success: bool) {
+        game.next_digit_span_trial();
+        while matches!(game.phase, GamePhase::ShowingSequence) {
+            game.advance(0.1);
+        }
+        for item in game.expected_input() {
+            game.process_input(item);
+        }
+        let result = RoundResult { success, ..game.take_finished_round().unwrap() };
+        game.record_digit_span_trial(&result);
+    }
+
+    #[test]
+    fn the_battery_stops_once_both_trials_at_a_length_fail() {
+        let mut game = MemoryGame::new();
+        game.start_digit_span(protocol(&["forward"])).unwrap();
+
+        // One failure at a length isn't enough to stop
+        for success in [true, true, true, false, false, true] {
+            trial(&mut game, success);
+            assert_eq!(game.digit_span().unwrap().stage, DigitSpanStage::Running);
+        }
+        assert_eq!(game.digit_span().unwrap().length, 5);
+        trial(&mut game, false);
+        trial(&mut game, false);
+
+        let digit_span = game.digit_span().unwrap();
+        assert_eq!(digit_span.stage, DigitSpanStage::Finished);
+        let score = &digit_span.scores[0];
+        assert_eq!(score.trials, [(2, true), (2, true), (3, true), (3, false), (4, false), (4, true), (5, false), (5, false)]);
+        assert_eq!(score.longest_span, 4);
+        assert_eq!(score.raw_total, 4);
+    }
+
+    #[test]
+    fn the_next_direction_follows_after_its_instructions() {
+        let mut game = MemoryGame::new();
+        game.start_digit_span(protocol(&["forward", "backward"])).unwrap();
+        trial(&mut game, false);
+        trial(&mut game, false);
+
+        let digit_span = game.digit_span().unwrap();
+        assert_eq!(digit_span.stage, DigitSpanStage::Instructions);
+        assert_eq!(digit_span.scores[0].longest_span, 0);
+
+        trial(&mut game, true);
+        let digit_span = game.digit_span().unwrap();
+        assert_eq!(digit_span.direction(), Some(&InputDirection::Reverse));
+        assert_eq!(digit_span.scores[1].trials, [(2, true)]);
+        assert_eq!(*game.input_direction(), InputDirection::Reverse);
+    }
+
+    #[test]
+    fn the_battery_stops_at_the_longest_length() {
+        let mut game = MemoryGame::new();
+        game.start_digit_span(DigitSpanProtocol { max_length: 3, ..protocol(&["forward"]) }).unwrap();
+        for _ in 0..4 {
+            trial(&mut game, true);
+        }
+        let digit_span = game.digit_span().unwrap();
+        assert_eq!(digit_span.stage, DigitSpanStage::Finished);
+        assert_eq!(digit_span.scores[0].longest_span, 3);
+    }
+
+    #[test]
+    fn protocols_need_known_directions() {
+        assert!(protocol(&[]).directions().is_err());
+        assert!(protocol(&["sideways"]).directions().is_err());
+        assert_eq!(
+            protocol(&["forward", "backward", "sequencing"]).directions(),
+            Ok(vec![InputDirection::Forward, InputDirection::Reverse, InputDirection::Sequencing])
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use rand::Rng;
+
+use crate::input_direction::InputDirection;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirectionPolicy {
+    Random,
+    Forward,
+    Reverse,
+    Alternate, // Forward on the first round, then switching every round
+    Sequencing,
+}
+
+impl DirectionPolicy {
+    // Challenge codes store the index, so new policies go at the end
+    pub const ALL: [DirectionPolicy; 5] = [
+        DirectionPolicy::Random,
+        DirectionPolicy::Forward,
+        DirectionPolicy::Reverse,
+        DirectionPolicy::Alternate,
+        DirectionPolicy::Sequencing,
+    ];
+
+    // The policy that always gives this direction
+    pub fn fixed(direction: &InputDirection) -> Self {
+        match direction {
+            InputDirection::Forward => DirectionPolicy::Forward,
+            InputDirection::Reverse => DirectionPolicy::Reverse,
+            InputDirection::Sequencing => DirectionPolicy::Sequencing,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "random" => Ok(DirectionPolicy::Random),
+            "forward" => Ok(DirectionPolicy::Forward),
+            "reverse" => Ok(DirectionPolicy::Reverse),
+            "alternate" => Ok(DirectionPolicy::Alternate),
+            "sequencing" => Ok(DirectionPolicy::Sequencing),
+            _ => Err(format!(
+                "unknown direction policy '{}', use random, forward, reverse, alternate or sequencing",
+                text
+            )),
+        }
+    }
+
+    pub fn short_name(&self) -> &str {
+        match self {
+            DirectionPolicy::Random => "random",
+            DirectionPolicy::Forward => "forward",
+            DirectionPolicy::Reverse => "reverse",
+            DirectionPolicy::Alternate => "alternate",
+            DirectionPolicy::Sequencing => "sequencing",
+        }
+    }
+
+    pub fn direction<R: Rng>(&self, rng: &mut R, round_number: u32) -> InputDirection {
+        match self {
+            DirectionPolicy::Random => {
+                if rng.gen_bool(0.5) {
+                    InputDirection::Forward
+                } else {
+                    InputDirection::Reverse
+                }
+            }
+            DirectionPolicy::Forward => InputDirection::Forward,
+            DirectionPolicy::Reverse => InputDirection::Reverse,
+            DirectionPolicy::Sequencing => InputDirection::Sequencing,
+            DirectionPolicy::Alternate => {
+                if round_number.is_multiple_of(2) {
+                    InputDirection::Forward
+                } else {
+                    InputDirection::Reverse
+                }
+            }
+        }
+    }
+}
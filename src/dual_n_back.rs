@@ -0,0 +1,122 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::char_type::CharType;
+use crate::n_back::{generate_matches, score_responses, NBackResults};
+
+// Stimuli appear on the outer cells of a 3x3 board, the centre is left empty
+const POSITION_COUNT: usize = 8;
+
+// Block accuracy needed on both streams to move N up, and below which N moves down
+const RAISE_ACCURACY: f64 = 0.9;
+const LOWER_ACCURACY: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MatchStream {
+    Position,
+    Character,
+}
+
+#[derive(Debug, Clone)]
+pub struct DualNBack {
+    pub n: usize,
+    pub stream_length: usize,
+    pub target_rate: f64,
+    pub adaptive: bool, // Adjust N after each block based on its accuracy
+    block_n: usize,
+    positions: Vec<usize>,
+    position_responses: Vec<bool>,
+    character_responses: Vec<bool>,
+}
+
+impl DualNBack {
+    pub fn new() -> Self {
+        Self {
+            n: 2,
+            stream_length: 22,
+            target_rate: 0.3,
+            adaptive: false,
+            block_n: 2,
+            positions: vec![],
+            position_responses: vec![],
+            character_responses: vec![],
+        }
+    }
+
+    // Generates both streams independently and returns the character one
+    pub fn generate_streams<R: Rng>(&mut self, rng: &mut R, alphabet: &Alphabet) -> Vec<CharType> {
+        self.positions = generate_matches(rng, self.n, self.stream_length, self.target_rate, |rng| {
+            rng.gen_range(0..POSITION_COUNT)
+        });
+        let characters = generate_matches(rng, self.n, self.stream_length, self.target_rate, |rng| {
+            alphabet.random_item(rng)
+        });
+
+        self.block_n = self.n;
+        self.position_responses = vec![false; self.stream_length];
+        self.character_responses = vec![false; self.stream_length];
+        characters
+    }
+
+    pub fn block_n(&self) -> usize {
+        self.block_n
+    }
+
+    pub fn respond(&mut self, stream: MatchStream, index: usize) {
+        let responses = match stream {
+            MatchStream::Position => &mut self.position_responses,
+            MatchStream::Character => &mut self.character_responses,
+        };
+        if let Some(response) = responses.get_mut(index) {
+            *response = true;
+        }
+    }
+
+    pub fn has_responded(&self, stream: MatchStream, index: usize) -> bool {
+        let responses = match stream {
+            MatchStream::Position => &self.position_responses,
+            MatchStream::Character => &self.character_responses,
+        };
+        responses.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn results(&self, stream: MatchStream, characters: &[CharType]) -> NBackResults {
+        match stream {
+            MatchStream::Position => score_responses(&self.positions, &self.position_responses, self.block_n),
+            MatchStream::Character => score_responses(characters, &self.character_responses, self.block_n),
+        }
+    }
+
+    // Applies the adaptive rule to the finished block, returns the N for the next one
+    pub fn adapt(&mut self, characters: &[CharType]) -> usize {
+        if !self.adaptive {
+            return self.n;
+        }
+
+        let worst_accuracy = self
+            .results(MatchStream::Position, characters)
+            .accuracy()
+            .min(self.results(MatchStream::Character, characters).accuracy());
+
+        if worst_accuracy >= RAISE_ACCURACY {
+            self.n = self.block_n + 1;
+        } else if worst_accuracy < LOWER_ACCURACY {
+            self.n = self.block_n.saturating_sub(1).max(1);
+        } else {
+            self.n = self.block_n;
+        }
+        self.n
+    }
+
+    // Which of the outer cells the item at index is shown in
+    pub fn position(&self, index: usize) -> Option<usize> {
+        self.positions.get(index).copied()
+    }
+}
+
+impl Default for DualNBack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
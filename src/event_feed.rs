@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::dual_n_back::MatchStream;
+use crate::game_event::GameEvent;
+use crate::game_mode::GameMode;
+use crate::memory_game::MemoryGame;
+use crate::replay::Action;
+use crate::websocket;
+
+// Round events as JSON over WebSocket, for stream overlays and other tools.
+//
+// Every message is one JSON object with the schema version and the event name:
+//
+//   {"version":1,"event":"round_started","mode":"sequence","variant":"serial-recall",
+//    "length":5,"direction":"forward","strict":false,"player":"ana"}
+//       mode is sequence, spatial-span, n-back, dual-n-back or complex-span; variant is only
+//       set for sequence rounds; length is the number of items; player is null for guests
+//   {"version":1,"event":"item_shown","index":0,"item":"7"}
+//       index counts from 0; blocks are numbered from 1 as on screen
+//   {"version":1,"event":"input_entered","ms":2310,"input":"7"}
+//       ms since the round started; input is the item typed or clicked, or one of delete,
+//       match, position-match, character-match, true, false, same, different
+//   {"version":1,"event":"round_finished","success":true,"score":100,"input_ms":1840}
+//       input_ms is null when the round had no answer phase
+//
+// Fields are only ever added within a version; anything else gets a new version number.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Overlays that stop reading are dropped rather than holding up the game
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FeedEvent {
+    RoundStarted {
+        mode: &'static str,
+        variant: Option<&'static str>,
+        length: usize,
+        direction: &'static str,
+        strict: bool,
+        player: Option<String>,
+    },
+    ItemShown {
+        index: usize,
+        item: String,
+    },
+    InputEntered {
+        ms: u64,
+        input: String,
+    },
+    RoundFinished {
+        success: bool,
+        score: u32,
+        input_ms: Option<u64>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a FeedEvent,
+}
+
+impl FeedEvent {
+    // None for engine events the feed leaves out
+    pub fn from_game_event(event: &GameEvent, game: &MemoryGame) -> Option<Self> {
+        match event {
+            GameEvent::RoundStarted { .. } => Some(FeedEvent::RoundStarted {
+                mode: game.game_mode.key(),
+                variant: (game.game_mode == GameMode::Sequence).then(|| game.test_variant.key()),
+                length: game.sequence().len(),
+                direction: game.input_direction().key(),
+                strict: !game.normal_mode,
+                player: game.profile.clone(),
+            }),
+            GameEvent::ItemShown(index) => Some(FeedEvent::ItemShown {
+                index: *index,
+                item: game.sequence().get(*index)?.as_char().to_string(),
+            }),
+            GameEvent::Input(keystroke) => Some(FeedEvent::InputEntered {
+                ms: keystroke.ms,
+                input: match &keystroke.action {
+                    Action::Item(item) => item.as_char().to_string(),
+                    Action::Delete => "delete".to_string(),
+                    Action::Match => "match".to_string(),
+                    Action::DualMatch(MatchStream::Position) => "position-match".to_string(),
+                    Action::DualMatch(MatchStream::Character) => "character-match".to_string(),
+                    Action::Equation(answer) => answer.to_string(),
+                    Action::Recognition(true) => "same".to_string(),
+                    Action::Recognition(false) => "different".to_string(),
+                },
+            }),
+            GameEvent::PhaseChanged(_) => None,
+            GameEvent::RoundFinished(result) => Some(FeedEvent::RoundFinished {
+                success: result.success,
+                score: result.score,
+                input_ms: result.input_duration_ms,
+            }),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&Message { version: SCHEMA_VERSION, event: self }).unwrap_or_default()
+    }
+}
+
+// WebSocket server every connected client gets the events from
+#[derive(Debug)]
+pub struct EventFeed {
+    pub address: String,
+    clients: Arc<Mutex<Vec<Sender<String>>>>, // To each client's writer thread
+}
+
+impl EventFeed {
+    pub fn open(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("could not listen on {}: {}", address, e))?;
+        let clients = Arc::new(Mutex::new(vec![]));
+        // The address actually bound, so a port of 0 gets its real number
+        let address = listener.local_addr().map(|address| address.to_string()).unwrap_or_else(|_| address.to_string());
+
+        let shared = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let shared = shared.clone();
+                // A slow handshake doesn't keep others waiting, and once it's done the same thread
+                // does the writing, so a client that stalls only holds up itself
+                thread::spawn(move || {
+                    if websocket::accept(&mut stream).is_err() {
+                        return;
+                    }
+                    let _ = stream.set_nodelay(true);
+                    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+
+                    let (sender, receiver) = mpsc::channel::<String>();
+                    shared.lock().unwrap().push(sender);
+                    for message in receiver {
+                        if websocket::write_text(&mut stream, &message).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self { address, clients })
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    pub fn publish(&self, game: &MemoryGame, events: &[GameEvent]) {
+        let messages: Vec<String> = events
+            .iter()
+            .filter_map(|event| FeedEvent::from_game_event(event, game))
+            .map(|event| event.to_json())
+            .collect();
+        if messages.is_empty() {
+            return;
+        }
+
+        // A client whose writer thread has given up is dropped here
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|sender| messages.iter().all(|message| sender.send(message.clone()).is_ok()));
+    }
+}
+
+impl MemoryGame {
+    pub fn start_event_feed(&mut self, address: &str) -> Result<(), String> {
+        self.event_feed = Some(EventFeed::open(address)?);
+        self.publish_events = true;
+        Ok(())
+    }
+
+    pub fn event_feed(&self) -> Option<&EventFeed> {
+        self.event_feed.as_ref()
+    }
+}
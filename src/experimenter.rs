@@ -0,0 +1,144 @@
+use std::time::Instant;
+
+use crate::game_event::GameEvent;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::round_result::RoundResult;
+
+// Lab sessions: the experimenter runs the trials from a second window while the participant's
+// window shows nothing but the trial itself
+
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub number: u32,
+    pub length: usize,
+    pub success: bool,
+    pub score: u32,
+    pub input_duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExperimenterConsole {
+    pub trials: Vec<Trial>,     // Finished this session, skipped ones aren't kept
+    pub skipped: u32,
+    pub paused_at: Option<Instant>,
+    pub aborted: bool,
+}
+
+impl ExperimenterConsole {
+    // Number of the trial being run or about to be, counting skipped ones
+    pub fn trial_number(&self) -> u32 {
+        self.trials.len() as u32 + self.skipped + 1
+    }
+
+    pub fn record(&mut self, result: &RoundResult) {
+        self.trials.push(Trial {
+            number: self.trial_number(),
+            length: result.length(),
+            success: result.success,
+            score: result.score,
+            input_duration_ms: result.input_duration_ms,
+        });
+    }
+
+    // Share of finished trials answered correctly, None before the first
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.trials.is_empty() {
+            return None;
+        }
+        Some(self.trials.iter().filter(|trial| trial.success).count() as f64 / self.trials.len() as f64)
+    }
+
+    pub fn mean_input_ms(&self) -> Option<f64> {
+        let times: Vec<u64> = self.trials.iter().filter_map(|trial| trial.input_duration_ms).collect();
+        if times.is_empty() {
+            return None;
+        }
+        Some(times.iter().sum::<u64>() as f64 / times.len() as f64)
+    }
+}
+
+impl MemoryGame {
+    pub fn open_experimenter_console(&mut self) {
+        if self.experimenter.is_none() {
+            self.experimenter = Some(ExperimenterConsole::default());
+        }
+    }
+
+    pub fn close_experimenter_console(&mut self) {
+        self.resume_trial();
+        self.experimenter = None;
+    }
+
+    pub fn experimenter(&self) -> Option<&ExperimenterConsole> {
+        self.experimenter.as_ref()
+    }
+
+    pub fn record_experimenter_trial(&mut self, result: &RoundResult) {
+        if let Some(console) = &mut self.experimenter {
+            console.record(result);
+        }
+    }
+
+    pub fn start_trial(&mut self) {
+        if let Some(console) = &mut self.experimenter {
+            console.aborted = false;
+        }
+        if self.digit_span.is_some() {
+            self.next_digit_span_trial();
+        } else if self.quest.is_some() {
+            self.next_quest_trial();
+        } else {
+            self.next_study_trial();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.experimenter.as_ref().is_some_and(|console| console.paused_at.is_some())
+    }
+
+    // Freezes the presentation and input; the pause doesn't count towards any timing
+    pub fn pause_trial(&mut self) {
+        let in_round = self.in_round();
+        if let Some(console) = &mut self.experimenter
+            && in_round
+            && console.paused_at.is_none() {
+            console.paused_at = Some(Instant::now());
+        }
+    }
+
+    pub fn resume_trial(&mut self) {
+        let Some(paused_at) = self.experimenter.as_mut().and_then(|console| console.paused_at.take()) else {
+            return;
+        };
+        let paused = paused_at.elapsed();
+        if let Some(start) = &mut self.round_start_time {
+            *start += paused;
+        }
+        if let Some(start) = &mut self.input_start_time {
+            *start += paused;
+        }
+    }
+
+    // Drops the trial without saving it and goes on with the next one the protocol has
+    pub fn skip_trial(&mut self) {
+        if !self.in_round() {
+            return;
+        }
+        self.resume_trial();
+        if let Some(console) = &mut self.experimenter {
+            console.skipped += 1;
+        }
+        self.start_trial();
+    }
+
+    // Ends the session: the trial being run is dropped and the participant sees that it's over
+    pub fn abort_session(&mut self) {
+        self.resume_trial();
+        if let Some(console) = &mut self.experimenter {
+            console.aborted = true;
+        }
+        self.take_finished_round();
+        self.phase = GamePhase::NotStarted;
+        self.emit(GameEvent::PhaseChanged(GamePhase::NotStarted));
+    }
+}
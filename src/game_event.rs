@@ -0,0 +1,40 @@
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::replay::Keystroke;
+use crate::round_result::RoundResult;
+
+// What happened in a round, in order, for anything following the game from outside
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    RoundStarted { seed: u64 },
+    ItemShown(usize),         // Index of the item that just came on screen
+    PhaseChanged(GamePhase),
+    Input(Keystroke),         // Every keystroke the round logs, deletions included
+    RoundFinished(Box<RoundResult>), // Boxed, rounds are much bigger than the other events
+}
+
+impl MemoryGame {
+    // Events are only kept while something picks them up
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        if self.publish_events {
+            self.events.push(event);
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // Hands what happened since the last call to whoever is following the game
+    pub fn send_round_events(&mut self) {
+        let events = self.take_events();
+        if let Some(broadcast) = &self.broadcast {
+            broadcast.publish(self, &events);
+        }
+        if let Some(event_feed) = &self.event_feed {
+            event_feed.publish(self, &events);
+        }
+        if let Some(markers) = &mut self.markers {
+            markers.queue(&events, self.round_start_time);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Sequence,
+    SpatialSpan,
+    NBack,
+    DualNBack,
+    ComplexSpan,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Sequence,
+        GameMode::SpatialSpan,
+        GameMode::NBack,
+        GameMode::DualNBack,
+        GameMode::ComplexSpan,
+    ];
+
+    pub fn short_name(&self) -> &str {
+        match self {
+            GameMode::Sequence => "Sequence",
+            GameMode::SpatialSpan => "Spatial Span",
+            GameMode::NBack => "N-Back",
+            GameMode::DualNBack => "Dual N-Back",
+            GameMode::ComplexSpan => "Complex Span",
+        }
+    }
+
+    // Name used in settings files and on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            GameMode::Sequence => "sequence",
+            GameMode::SpatialSpan => "spatial-span",
+            GameMode::NBack => "n-back",
+            GameMode::DualNBack => "dual-n-back",
+            GameMode::ComplexSpan => "complex-span",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| mode.key() == text.to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown game mode '{}'", text))
+    }
+
+    // Sequencing puts items in character order, which spatial span blocks don't show during input
+    pub fn has_sequencing(&self) -> bool {
+        matches!(self, GameMode::Sequence | GameMode::ComplexSpan)
+    }
+
+    // Continuous modes collect responses while items stream instead of after presentation
+    pub fn is_continuous(&self) -> bool {
+        matches!(self, GameMode::NBack | GameMode::DualNBack)
+    }
+}
@@ -0,0 +1,101 @@
+use eframe::egui;
+
+use memory_game::block_grid::{BlockGrid, GRID_COLUMNS, GRID_ROWS};
+use memory_game::char_type::CharType;
+use memory_game::dual_n_back::DualNBack;
+
+// Draws the board and returns the index of a block clicked this frame, if any
+pub fn show_block_grid(
+    ui: &mut egui::Ui,
+    grid: &BlockGrid,
+    highlighted: Option<usize>,
+    interactive: bool,
+    show_labels: bool,
+) -> Option<usize> {
+    let width = ui.available_width().min(400.0);
+    let size = egui::vec2(width, width * GRID_ROWS as f32 / GRID_COLUMNS as f32);
+    let sense = if interactive { egui::Sense::click() } else { egui::Sense::hover() };
+    let (rect, response) = ui.allocate_exact_size(size, sense);
+
+    let block_size = egui::Vec2::splat(rect.width() / GRID_COLUMNS as f32 * 0.7);
+    let block_rects: Vec<egui::Rect> = grid
+        .positions()
+        .iter()
+        .map(|(x, y)| {
+            let center = rect.min + egui::vec2(x * rect.width(), y * rect.height());
+            egui::Rect::from_center_size(center, block_size)
+        })
+        .collect();
+
+    let pointer_pos = response.interact_pointer_pos();
+    let pressed_block = if interactive && response.is_pointer_button_down_on() {
+        pointer_pos.and_then(|pos| block_rects.iter().position(|r| r.contains(pos)))
+    } else {
+        None
+    };
+
+    let painter = ui.painter_at(rect);
+    for (i, block_rect) in block_rects.iter().enumerate() {
+        let fill = if highlighted == Some(i) || pressed_block == Some(i) {
+            egui::Color32::from_rgb(255, 200, 0)
+        } else {
+            egui::Color32::from_rgb(60, 90, 160)
+        };
+        painter.rect_filled(*block_rect, 6.0, fill);
+
+        if show_labels {
+            painter.text(
+                block_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                (i + 1).to_string(),
+                egui::FontId::proportional(20.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    if interactive && response.clicked() {
+        pointer_pos.and_then(|pos| block_rects.iter().position(|r| r.contains(pos)))
+    } else {
+        None
+    }
+}
+
+// Draws the 3x3 board with the current item in its cell
+pub fn show_dual_n_back_board(ui: &mut egui::Ui, dual_n_back: &DualNBack, index: usize, item: Option<&CharType>) {
+    let size = ui.available_width().min(300.0);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let cell_size = size / 3.0;
+
+    let active_cell = item.and(dual_n_back.position(index)).map(|position| {
+        // Skip over the centre cell
+        if position >= 4 { position + 1 } else { position }
+    });
+
+    for cell in 0..9 {
+        let cell_rect = egui::Rect::from_min_size(
+            rect.min + egui::vec2((cell % 3) as f32 * cell_size, (cell / 3) as f32 * cell_size),
+            egui::Vec2::splat(cell_size),
+        )
+        .shrink(4.0);
+
+        let fill = if active_cell == Some(cell) {
+            egui::Color32::from_rgb(60, 90, 160)
+        } else {
+            egui::Color32::from_gray(40)
+        };
+        painter.rect_filled(cell_rect, 6.0, fill);
+
+        if active_cell == Some(cell)
+            && let Some(item) = item {
+            painter.text(
+                cell_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                item.as_char(),
+                egui::FontId::proportional(cell_size * 0.5),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
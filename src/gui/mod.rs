@@ -0,0 +1,2429 @@
+use eframe::egui;
+
+use memory_game::alphabet::Alphabet;
+use memory_game::analytics::{Chart, all_charts, export};
+use memory_game::challenge::{Challenge, daily_date, share_text};
+use memory_game::char_type::CharType;
+use memory_game::complex_span::ACCURACY_THRESHOLDS;
+use memory_game::digit_span::{DigitSpan, DigitSpanProtocol, DigitSpanStage};
+use memory_game::direction_policy::DirectionPolicy;
+use memory_game::dual_n_back::MatchStream;
+use memory_game::game_mode::GameMode;
+use memory_game::hot_seat::{HotSeatSetup, HotSeatStage, SequenceSharing, MAX_PLAYERS, MIN_PLAYERS};
+use memory_game::input_direction::InputDirection;
+use memory_game::memory_game::{GamePhase, MemoryGame};
+use memory_game::leaderboard::{Category, Filter, TOP_ENTRIES, is_personal_best};
+use memory_game::leaderboard_client::{Rankings, Replies, ServerReply, fetch_in_background, submit_in_background};
+use memory_game::n_back::MAX_STREAM_LENGTH;
+use memory_game::norms::{NormedScore, Norms, digit_span_measures};
+use memory_game::profile::personal_bests;
+use memory_game::quest::{Quest, QuestParameter, QuestSetup, QuestStage};
+use memory_game::race::{RaceOutcome, RaceRole, RaceStage};
+use memory_game::replay::Action;
+use memory_game::round_result::{RoundResult, format_date};
+use memory_game::recall_test::TestVariant;
+use memory_game::settings::Settings;
+use memory_game::spectator::ViewerStatus;
+use memory_game::study::StudyStage;
+
+mod boards;
+
+use boards::{show_block_grid, show_dual_n_back_board};
+
+// The game in a window, with everything only the window needs
+pub struct MemoryGameApp {
+    game: MemoryGame,
+    show_settings: bool,
+    custom_alphabet_text: String,      // Being edited in the settings panel
+    profile_name_text: String,         // Name being typed on the profile screen
+    renaming_profile: Option<String>,
+    deleting_profile: Option<String>,  // Waiting for the delete to be confirmed
+    show_leaderboard: bool,
+    leaderboard_filter: Filter,
+    show_analytics: bool,
+    analytics_note: Option<String>,    // Where the charts were last exported to
+    record_note: Option<String>,       // Personal best or top entry set by the last round
+    server_replies: Replies,
+    server_text: String,               // Being edited in the settings panel
+    server_note: Option<String>,       // Place on the server leaderboard of the last round
+    show_server_rankings: bool,
+    server_rankings: Option<(Filter, Option<Rankings>)>, // None while waiting
+    daily_share: Option<String>,       // Result to paste into chat after a daily challenge
+    challenge_code_text: String,       // Being typed into the Play challenge field
+    hot_seat_setup: Option<HotSeatSetup>, // Players being picked for a hot seat game
+    show_race_setup: bool,
+    race_address_text: String,
+    visible_items: Vec<usize>,         // Items drawn in the current frame
+    quest_setup: Option<QuestSetup>,
+}
+
+impl MemoryGameApp {
+    pub fn new(game: MemoryGame) -> Self {
+        Self {
+            game,
+            show_settings: false,
+            custom_alphabet_text: String::new(),
+            profile_name_text: String::new(),
+            renaming_profile: None,
+            deleting_profile: None,
+            show_leaderboard: false,
+            leaderboard_filter: Filter::default(),
+            show_analytics: false,
+            analytics_note: None,
+            record_note: None,
+            server_replies: Replies::default(),
+            server_text: String::new(),
+            server_note: None,
+            show_server_rankings: false,
+            server_rankings: None,
+            daily_share: None,
+            challenge_code_text: String::new(),
+            hot_seat_setup: None,
+            show_race_setup: false,
+            race_address_text: "127.0.0.1:7880".to_string(),
+            visible_items: vec![],
+            quest_setup: None,
+        }
+    }
+
+    fn handle_key_input(&mut self, ctx: &egui::Context) {
+        // Typing into a text field (settings, profile names) isn't game input
+        if ctx.wants_keyboard_input()
+            || self.game.show_profiles
+            || self.show_leaderboard
+            || self.show_analytics
+            || self.show_race_setup
+            || self.game.session_setup().is_some()
+            || self.quest_setup.is_some()
+            || self.game.watching()
+            || self.game.is_paused() {
+            return;
+        }
+
+        ctx.input(|i| {
+            for event in &i.raw.events {
+                if let egui::Event::Key { key, pressed, modifiers: _, physical_key: _, repeat: _ } = event
+                    && *pressed {
+                    match key {
+                        egui::Key::Backspace | egui::Key::Delete => {
+                            self.game.delete_last();
+                        }
+                        egui::Key::Space => self.game.respond_match(),
+                        _ => {
+                            if let Some(c) = key_char(*key) {
+                                self.game.press_char(c);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Challenges bring their own settings, hot seat players all play by the same ones,
+    // in a race the host's settings are used, in a study the condition's, and the digit span
+    // battery and threshold estimates set their own
+    fn settings_locked(&self) -> bool {
+        self.game.challenge().is_some()
+            || self.game.hot_seat().is_some()
+            || self.game.race().is_some()
+            || self.game.study().is_some()
+            || self.game.digit_span().is_some()
+            || self.game.quest().is_some()
+            || self.game.watching()
+    }
+
+    fn save_finished_round(&mut self) {
+        let Some(mut result) = self.game.take_finished_round() else {
+            return;
+        };
+        // In hot seat the round belongs to whoever's turn it was
+        result.profile = match self.game.hot_seat() {
+            Some(hot_seat) => hot_seat.current_player().profile.clone(),
+            None => self.game.profile.clone(),
+        };
+        self.game.record_experimenter_trial(&result);
+        self.game.record_study_trial();
+        self.game.record_digit_span_trial(&result);
+        self.game.record_quest_trial(&result);
+
+        if let Some(date) = &result.daily {
+            // Only the first attempt at a daily challenge counts, later ones are practice
+            if !self.game.daily_attempt_counts() {
+                let first = self.game.history().iter().find(|other| other.daily.as_ref() == Some(date));
+                self.daily_share = first.map(share_text);
+                self.record_note = Some("Only your first attempt at today's challenge counts".to_string());
+                if let Some(path) = &self.game.results_path
+                    && let Err(e) = result.append_to(path) {
+                    self.game.warning = Some(format!("Could not save the result to {}: {}", path.display(), e));
+                }
+                return;
+            }
+            self.daily_share = Some(share_text(&result));
+        }
+
+        if let (Some(store), Some(name)) = (&self.game.profiles, &result.profile) {
+            let path = store.history_path(name);
+            if let Err(e) = result.append_to(&path) {
+                self.game.warning = Some(format!("Could not save the result to {}: {}", path.display(), e));
+            }
+
+            // Guest rounds aren't kept, so they don't make the leaderboard either
+            let place = self.game.leaderboard.place(&result).filter(|place| *place <= TOP_ENTRIES);
+            if result.success && self.game.hot_seat().is_none() {
+                self.record_note = match (is_personal_best(self.game.history(), &result), place) {
+                    (true, Some(place)) => Some(format!("New personal best, #{} on the leaderboard!", place)),
+                    (true, None) => Some("New personal best!".to_string()),
+                    (false, Some(place)) => Some(format!("#{} on the leaderboard!", place)),
+                    (false, None) => None,
+                };
+            }
+            self.game.leaderboard.add(result.clone());
+
+            // Only correct rounds are ranked, on the server as here
+            if result.success
+                && let Some(address) = &self.game.leaderboard_server {
+                submit_in_background(address.clone(), result.clone(), self.server_replies.clone());
+            }
+        }
+
+        if let Some(path) = &self.game.results_path
+            && let Err(e) = result.append_to(path) {
+            self.game.warning = Some(format!("Could not save the result to {}: {}", path.display(), e));
+        }
+
+        self.game.finish_race(&result);
+
+        self.game.record_hot_seat_turn(&result);
+        self.game.add_to_history(result);
+    }
+
+    fn take_server_replies(&mut self) {
+        let replies: Vec<ServerReply> = self.server_replies.lock().unwrap().drain(..).collect();
+        for reply in replies {
+            match reply {
+                ServerReply::Submitted(Ok(place)) => {
+                    if place <= TOP_ENTRIES {
+                        self.server_note = Some(format!("#{} on the server leaderboard!", place));
+                    }
+                }
+                ServerReply::Submitted(Err(e)) => {
+                    self.game.warning = Some(format!("Could not submit the round to the leaderboard server: {}", e));
+                }
+                ServerReply::Rankings(filter, rankings) => self.server_rankings = Some((filter, Some(rankings))),
+            }
+        }
+    }
+
+    fn show_profile_screen(&mut self, ui: &mut egui::Ui) {
+        let Some(store) = self.game.profiles.clone() else {
+            return;
+        };
+
+        ui.heading("Who is playing?");
+
+        let mut error = None;
+        egui::Grid::new("profiles_grid").num_columns(2).show(ui, |ui| {
+            for name in store.list() {
+                if self.renaming_profile.as_deref() == Some(name.as_str()) {
+                    ui.text_edit_singleline(&mut self.profile_name_text);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save name").clicked() {
+                            let new_name = self.profile_name_text.trim().to_string();
+                            match store.rename(&name, &new_name) {
+                                Ok(()) => {
+                                    self.game.leaderboard.rename_profile(&name, &new_name);
+                                    if self.game.profile.as_deref() == Some(name.as_str()) {
+                                        self.game.profile = Some(new_name.clone());
+                                        self.game.settings_path = Some(store.settings_path(&new_name));
+                                    }
+                                    self.renaming_profile = None;
+                                    self.profile_name_text.clear();
+                                }
+                                Err(e) => error = Some(e),
+                            }
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.renaming_profile = None;
+                            self.profile_name_text.clear();
+                        }
+                    });
+                } else {
+                    if self.game.profile.as_deref() == Some(name.as_str()) {
+                        ui.strong(format!("{} (active)", name));
+                    } else {
+                        ui.label(&name);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Play").clicked() {
+                            self.game.switch_profile(Some(name.clone()));
+                            self.game.show_profiles = false;
+                        }
+                        if ui.button("Rename").clicked() {
+                            self.renaming_profile = Some(name.clone());
+                            self.profile_name_text = name.clone();
+                        }
+                        if self.deleting_profile.as_deref() == Some(name.as_str()) {
+                            if ui.button("Really delete?").clicked() {
+                                match store.delete(&name) {
+                                    Ok(()) => {
+                                        self.game.leaderboard.remove_profile(&name);
+                                        if self.game.profile.as_deref() == Some(name.as_str()) {
+                                            self.game.switch_profile(None);
+                                        }
+                                    }
+                                    Err(e) => error = Some(e),
+                                }
+                                self.deleting_profile = None;
+                            }
+                        } else if ui.button("Delete").clicked() {
+                            self.deleting_profile = Some(name.clone());
+                        }
+                    });
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+
+        if self.renaming_profile.is_none() {
+            ui.horizontal(|ui| {
+                ui.label("New profile:");
+                ui.text_edit_singleline(&mut self.profile_name_text);
+                if ui.button("Create").clicked() {
+                    let name = self.profile_name_text.trim().to_string();
+                    match store.create(&name) {
+                        Ok(()) => {
+                            self.game.switch_profile(Some(name));
+                            self.profile_name_text.clear();
+                            self.game.show_profiles = false;
+                        }
+                        Err(e) => error = Some(e),
+                    }
+                }
+            });
+        }
+
+        if ui.button("Play as guest").clicked() {
+            self.game.switch_profile(None);
+            self.game.show_profiles = false;
+        }
+
+        if let Some(e) = error {
+            self.game.warning = Some(e);
+        }
+    }
+
+    fn show_personal_bests(&self, ui: &mut egui::Ui) {
+        let bests = personal_bests(self.game.history());
+        if bests.is_empty() {
+            ui.label("No successful rounds yet");
+            return;
+        }
+
+        egui::Grid::new("personal_bests").show(ui, |ui| {
+            for (mode, result) in bests {
+                ui.label(mode);
+                ui.label(format!("{} points", result.score));
+                ui.label(format!("{} items", result.length()));
+                ui.colored_label(direction_color(&result.direction), result.direction.short_name());
+                if let Some(millis) = result.input_duration_ms {
+                    ui.label(format!("{:.2} s", millis as f32 / 1000.0));
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn show_hot_seat_setup(&mut self, ui: &mut egui::Ui) {
+        let Some(setup) = &mut self.hot_seat_setup else {
+            return;
+        };
+
+        ui.heading("Hot Seat");
+        ui.label(format!("{} to {} players take turns on this computer", MIN_PLAYERS, MAX_PLAYERS));
+
+        let mut error = None;
+        let mut removed = None;
+        egui::Grid::new("hot_seat_players").num_columns(2).show(ui, |ui| {
+            for (index, player) in setup.players.iter().enumerate() {
+                ui.label(format!("{}. {}{}", index + 1, player.name, if player.profile.is_some() { "" } else { " (guest)" }));
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            setup.players.remove(index);
+        }
+
+        if let Some(store) = &self.game.profiles {
+            let available: Vec<String> = store
+                .list()
+                .into_iter()
+                .filter(|name| !setup.players.iter().any(|player| player.profile.as_ref() == Some(name)))
+                .collect();
+            if !available.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("Add profile:");
+                    for name in available {
+                        if ui.button(&name).clicked()
+                            && let Err(e) = setup.add_player(&name, Some(name.clone())) {
+                            error = Some(e);
+                        }
+                    }
+                });
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Add guest:");
+            ui.text_edit_singleline(&mut setup.name_text);
+            if ui.button("Add").clicked() {
+                let name = setup.name_text.clone();
+                match setup.add_player(&name, None) {
+                    Ok(()) => setup.name_text.clear(),
+                    Err(e) => error = Some(e),
+                }
+            }
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut setup.sharing, SequenceSharing::Same, "Same sequence for everyone");
+            ui.radio_value(&mut setup.sharing, SequenceSharing::Independent, "Own sequence each");
+        });
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut setup.elimination, false, "Rounds:");
+            ui.add_enabled(!setup.elimination, egui::Slider::new(&mut setup.rounds, 1..=10));
+            ui.radio_value(&mut setup.elimination, true, "Elimination (sequence grows each round)");
+        });
+        ui.label(format!(
+            "Game: {}, {} items, {}",
+            self.game.game_mode.short_name(),
+            self.game.sequence_length,
+            if self.game.normal_mode { "Normal" } else { "Strict" }
+        ));
+        let playable = !self.game.game_mode.is_continuous();
+        if !playable {
+            ui.label(format!("{} rounds can't be won or lost, pick another game type in the settings", self.game.game_mode.short_name()));
+        }
+
+        let mut start = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            start = ui.add_enabled(playable && setup.players.len() >= MIN_PLAYERS, egui::Button::new("Start")).clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+
+        if start {
+            let setup = setup.clone();
+            match self.game.start_hot_seat(&setup) {
+                Ok(()) => self.hot_seat_setup = None,
+                Err(e) => error = Some(e),
+            }
+        }
+        if cancel {
+            self.hot_seat_setup = None;
+        }
+
+        if let Some(e) = error {
+            self.game.warning = Some(e);
+        }
+    }
+
+    // Everything of a hot seat game but the turns themselves
+    fn show_hot_seat_screen(&mut self, ui: &mut egui::Ui) {
+        let Some(hot_seat) = self.game.hot_seat_mut() else {
+            return;
+        };
+
+        let mut ready = false;
+        match hot_seat.stage {
+            HotSeatStage::WaitingForPlayer => {
+                ui.heading(format!("Round {}", hot_seat.round()));
+                ui.add_space(20.0);
+                ui.heading(format!("Pass the computer to {}", hot_seat.current_player().name));
+                ui.add_space(20.0);
+                if ui.button(format!("{} is ready", hot_seat.current_player().name)).clicked() {
+                    ready = true;
+                }
+            }
+            HotSeatStage::RoundSummary | HotSeatStage::Finished => {
+                let finished = hot_seat.stage == HotSeatStage::Finished;
+                if finished {
+                    let winners: Vec<&str> = hot_seat.winners().iter().map(|player| player.name.as_str()).collect();
+                    if winners.len() == 1 {
+                        ui.heading(format!("{} wins!", winners[0]));
+                    } else {
+                        ui.heading(format!("It's a tie between {}!", winners.join(" and ")));
+                    }
+                } else {
+                    ui.heading(format!("Round {} results", hot_seat.round()));
+                }
+
+                if finished {
+                    egui::Grid::new("hot_seat_standings").num_columns(4).striped(true).show(ui, |ui| {
+                        ui.strong("Player");
+                        ui.strong("Rounds correct");
+                        ui.strong("Total score");
+                        ui.strong("");
+                        ui.end_row();
+
+                        for player in hot_seat.standings() {
+                            ui.label(&player.name);
+                            ui.label(format!("{} of {}", player.rounds_correct, hot_seat.round()));
+                            ui.label(format!("{}", player.total_score));
+                            if let Some(round) = player.eliminated_in {
+                                ui.label(format!("out in round {}", round));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                } else {
+                    egui::Grid::new("hot_seat_summary").num_columns(5).striped(true).show(ui, |ui| {
+                        ui.strong("Player");
+                        ui.strong("Correct");
+                        ui.strong("Time");
+                        ui.strong("Score");
+                        ui.strong("Total");
+                        ui.end_row();
+
+                        for turn in hot_seat.turns() {
+                            let player = &hot_seat.players[turn.player];
+                            ui.label(&player.name);
+                            if turn.success {
+                                ui.colored_label(egui::Color32::GREEN, "yes");
+                            } else {
+                                ui.colored_label(egui::Color32::RED, "no");
+                            }
+                            match turn.input_duration_ms {
+                                Some(millis) => ui.label(format!("{:.2} s", millis as f32 / 1000.0)),
+                                None => ui.label("-"),
+                            };
+                            ui.label(format!("{}", turn.score));
+                            if player.eliminated_in == Some(hot_seat.round()) {
+                                ui.label(format!("{} (out)", player.total_score));
+                            } else {
+                                ui.label(format!("{}", player.total_score));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                }
+
+                ui.add_space(10.0);
+                if !finished && ui.button("Next").clicked() {
+                    hot_seat.continue_after_summary();
+                }
+            }
+            HotSeatStage::Playing => {}
+        }
+
+        if ready {
+            self.game.start_hot_seat_turn();
+        }
+
+        ui.add_space(20.0);
+        if ui.button("Leave hot seat").clicked() {
+            self.game.quit_hot_seat();
+        }
+    }
+
+    fn show_race_setup(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Race");
+        ui.label("Race someone on another computer: you both get the same sequence at the same moment");
+        ui.label("and the first to enter it correctly wins. The host's settings are used.");
+
+        ui.horizontal(|ui| {
+            ui.label("Address:");
+            ui.text_edit_singleline(&mut self.race_address_text);
+        });
+        ui.label("To host, 0.0.0.0:PORT lets other computers in; to join, use the host's IP and port");
+
+        let address = self.race_address_text.trim().to_string();
+        ui.horizontal(|ui| {
+            if ui.button("Host").clicked() {
+                match self.game.host_race(&address) {
+                    Ok(()) => {
+                        self.game.warning = None;
+                        self.show_race_setup = false;
+                    }
+                    Err(e) => self.game.warning = Some(format!("Race: {}", e)),
+                }
+            }
+            if ui.button("Join").clicked() {
+                self.game.join_race(&address);
+                self.game.warning = None;
+                self.show_race_setup = false;
+            }
+            if ui.button("Cancel").clicked() {
+                self.show_race_setup = false;
+            }
+        });
+    }
+
+    fn show_session_setup(&mut self, ui: &mut egui::Ui) {
+        // Until there's a session the setup is the only way in
+        let can_cancel = self.game.session().is_some();
+        let Some(setup) = self.game.session_setup_mut() else {
+            return;
+        };
+
+        ui.heading("Session");
+        egui::Grid::new("session_fields").num_columns(2).show(ui, |ui| {
+            ui.label("Participant ID:");
+            ui.text_edit_singleline(&mut setup.participant_text);
+            ui.end_row();
+            ui.label("Session number:");
+            ui.text_edit_singleline(&mut setup.session_text);
+            ui.end_row();
+            ui.label("Age (optional):");
+            ui.text_edit_singleline(&mut setup.age_text);
+            ui.end_row();
+            ui.label("Condition (optional):");
+            ui.text_edit_singleline(&mut setup.condition_text);
+            ui.end_row();
+            ui.label("Notes (optional):");
+            ui.text_edit_multiline(&mut setup.notes_text);
+            ui.end_row();
+        });
+
+        if let Some(consent) = &setup.consent {
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                ui.label(consent);
+            });
+            ui.checkbox(&mut setup.consent_given, "I have read the above and agree to take part");
+        }
+
+        if let Some(error) = &setup.error {
+            ui.colored_label(egui::Color32::YELLOW, error);
+        }
+
+        let mut start = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            start = ui.button("Start session").clicked();
+            cancel = can_cancel && ui.button("Cancel").clicked();
+        });
+
+        if start {
+            let started = setup.validate().and_then(|session| self.game.start_session(session));
+            if let (Err(e), Some(setup)) = (started, self.game.session_setup_mut()) {
+                setup.error = Some(e);
+            }
+        }
+        if cancel {
+            self.game.close_session_setup();
+        }
+    }
+
+    // Between blocks and after the last one
+    fn show_study_screen(&mut self, ui: &mut egui::Ui) {
+        let Some(study) = self.game.study() else {
+            return;
+        };
+
+        match study.stage {
+            StudyStage::Break if study.block == 0 => {
+                ui.heading("Ready?");
+                ui.label(format!(
+                    "There are {} blocks of {} trials, with a break after each block.",
+                    study.block_count(),
+                    study.design.trials_per_block
+                ));
+            }
+            StudyStage::Break => {
+                ui.heading(format!("Block {} of {} done", study.block, study.block_count()));
+                ui.label("Take a short break. Continue when you're ready for the next block.");
+            }
+            StudyStage::Finished | StudyStage::Running => {
+                ui.heading("All done, thank you!");
+                ui.label("Please let the experimenter know you've finished.");
+            }
+        }
+
+        let on_break = study.stage == StudyStage::Break;
+        ui.add_space(20.0);
+        ui.horizontal(|ui| {
+            if on_break && ui.button("Continue").clicked() {
+                self.game.next_study_trial();
+            }
+            if ui.button("Leave study").clicked() {
+                self.game.leave_study();
+            }
+        });
+    }
+
+    // Instructions before each direction and the scores at the end
+    fn show_digit_span_screen(&mut self, ui: &mut egui::Ui) {
+        let Some(digit_span) = self.game.digit_span() else {
+            return;
+        };
+
+        let stage = digit_span.stage;
+        match stage {
+            DigitSpanStage::Instructions => {
+                let Some(direction) = digit_span.directions.get(digit_span.scores.len()) else {
+                    return;
+                };
+                ui.heading(format!("Digit Span: {}", direction.short_name()));
+                ui.label(DigitSpan::instructions(direction));
+                ui.label(format!(
+                    "The lists start at {} digits and get longer; there are {} at each length.",
+                    digit_span.protocol.start_length, digit_span.protocol.trials_per_length
+                ));
+            }
+            DigitSpanStage::Finished | DigitSpanStage::Running => {
+                ui.heading("Digit Span complete");
+                show_digit_span_scores(ui, digit_span, self.game.norms(), self.game.age());
+            }
+        }
+
+        ui.add_space(20.0);
+        ui.horizontal(|ui| {
+            if stage == DigitSpanStage::Instructions && ui.button("Begin").clicked() {
+                self.game.next_digit_span_trial();
+            }
+            let leave = if stage == DigitSpanStage::Finished { "Done" } else { "Stop battery" };
+            if ui.button(leave).clicked() {
+                self.game.leave_digit_span();
+            }
+        });
+    }
+
+    fn show_quest_setup(&mut self, ui: &mut egui::Ui) {
+        let Some(setup) = &mut self.quest_setup else {
+            return;
+        };
+
+        ui.heading("Find Threshold");
+        ui.label("Rounds adapt to find the length, or display time, you get right at the target rate.");
+        ui.label(format!(
+            "Game: {}, {} items, {} ms per item",
+            self.game.game_mode.short_name(),
+            self.game.sequence_length,
+            (self.game.item_display_secs * 1000.0).round()
+        ));
+        ui.horizontal(|ui| {
+            ui.label("Vary:");
+            ui.radio_value(&mut setup.parameter, QuestParameter::Length, "Length");
+            ui.radio_value(&mut setup.parameter, QuestParameter::Speed, "Display time");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Target:");
+            ui.radio_value(&mut setup.target, 0.5, "50% correct");
+            ui.radio_value(&mut setup.target, 0.75, "75% correct");
+        });
+
+        let setup = *setup;
+        ui.horizontal(|ui| {
+            if ui.button("Start").clicked() {
+                match self.game.start_quest(setup.parameter, setup.target) {
+                    Ok(()) => {
+                        self.quest_setup = None;
+                        self.game.next_quest_trial();
+                    }
+                    Err(e) => self.game.warning = Some(format!("Threshold: {}", e)),
+                }
+            }
+            if ui.button("Cancel").clicked() {
+                self.quest_setup = None;
+            }
+        });
+    }
+
+    fn show_quest_result(&mut self, ui: &mut egui::Ui) {
+        let Some(quest) = self.game.quest() else {
+            return;
+        };
+
+        ui.heading("Threshold found");
+        show_quest_estimate(ui, quest);
+        ui.add_space(20.0);
+        if ui.button("Done").clicked() {
+            self.game.leave_quest();
+        }
+    }
+
+    // Everything of a race but the round itself
+    fn show_race_screen(&mut self, ui: &mut egui::Ui) {
+        let Some(race) = self.game.race() else {
+            return;
+        };
+        let opponent = race.opponent_name.clone().unwrap_or_else(|| "your opponent".to_string());
+
+        let mut start = false;
+        match &race.stage {
+            RaceStage::Connecting => match race.role {
+                RaceRole::Host => {
+                    ui.heading(format!("Waiting for an opponent on {}", race.address));
+                }
+                RaceRole::Joiner => {
+                    ui.heading(format!("Connecting to {}...", race.address));
+                }
+            },
+            RaceStage::Lobby => {
+                ui.heading(format!("Racing {}", opponent));
+                match race.role {
+                    RaceRole::Host => start = ui.button("Start race").clicked(),
+                    RaceRole::Joiner => {
+                        ui.label(format!("Waiting for {} to start the race", opponent));
+                    }
+                }
+            }
+            RaceStage::Countdown(_) => {
+                ui.heading(format!("Racing {}", opponent));
+                ui.heading(egui::RichText::new(race.countdown().unwrap_or(0).to_string()).size(64.0));
+            }
+            RaceStage::Done(outcome) => {
+                match outcome {
+                    RaceOutcome::Won => ui.colored_label(egui::Color32::GREEN, egui::RichText::new("You won the race!").heading()),
+                    RaceOutcome::Lost => ui.colored_label(egui::Color32::RED, egui::RichText::new(format!("{} won the race", opponent)).heading()),
+                    RaceOutcome::NoWinner => ui.heading("No winner this time"),
+                };
+                if matches!(self.game.phase(), GamePhase::Success) {
+                    ui.label(format!("Score: {}", self.game.score()));
+                }
+                match race.role {
+                    RaceRole::Host => start = ui.button("Race again").clicked(),
+                    RaceRole::Joiner => {
+                        ui.label(format!("Waiting for {} to start another race", opponent));
+                    }
+                }
+            }
+            RaceStage::Disconnected(reason) => {
+                ui.heading("The race is over");
+                ui.label(reason);
+            }
+            RaceStage::Racing => {}
+        }
+
+        if start && let Err(e) = self.game.start_race() {
+            self.game.warning = Some(format!("Race: {}", e));
+        }
+
+        ui.add_space(20.0);
+        if ui.button("Leave race").clicked() {
+            self.game.leave_race();
+        }
+    }
+
+    // Second window for the experimenter, kept out of the participant's sight
+    fn show_experimenter_console(&mut self, ctx: &egui::Context) {
+        if self.game.experimenter().is_none() {
+            return;
+        }
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("experimenter_console"),
+            egui::ViewportBuilder::default()
+                .with_title("Experimenter Console")
+                .with_inner_size([480.0, 600.0]),
+            |ctx, class| {
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.game.close_experimenter_console();
+                    return;
+                }
+                // Without multi-window support the console opens inside the main window
+                if class == egui::ViewportClass::Embedded {
+                    egui::Window::new("Experimenter Console").show(ctx, |ui| self.show_console_contents(ui));
+                } else {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, |ui| self.show_console_contents(ui));
+                    });
+                }
+            },
+        );
+    }
+
+    fn show_console_contents(&mut self, ui: &mut egui::Ui) {
+        let Some(console) = self.game.experimenter() else {
+            return;
+        };
+
+        ui.heading(format!("Trial {}", console.trial_number()));
+        match self.game.session() {
+            Some(session) => ui.label(format!(
+                "Participant {}, session {}{}",
+                session.participant_id,
+                session.session,
+                session.condition.as_ref().map(|condition| format!(", {}", condition)).unwrap_or_default()
+            )),
+            None => ui.label("No session details"),
+        };
+        if let Some(quest) = self.game.quest() {
+            ui.separator();
+            ui.strong("Threshold");
+            show_quest_estimate(ui, quest);
+            ui.separator();
+        }
+        if let Some(digit_span) = self.game.digit_span() {
+            ui.separator();
+            ui.strong("Digit Span");
+            show_digit_span_scores(ui, digit_span, self.game.norms(), self.game.age());
+            ui.separator();
+        }
+        if let Some(study) = self.game.study() {
+            ui.label(match study.stage {
+                StudyStage::Finished => format!("Study {} finished", study.design.name),
+                _ => format!(
+                    "Study {}: block {} of {} ({}), {} of {} trials done{}",
+                    study.design.name,
+                    study.block + 1,
+                    study.block_count(),
+                    study.condition().name,
+                    study.trials_done,
+                    study.design.trials_per_block,
+                    if study.stage == StudyStage::Break { ", break" } else { "" }
+                ),
+            });
+        }
+        ui.label(format!(
+            "{}{}, {} items, {}, {}",
+            self.game.game_mode.short_name(),
+            if self.game.game_mode == GameMode::Sequence { format!(" ({})", self.game.test_variant.short_name()) } else { String::new() },
+            self.game.sequence().len(),
+            self.game.input_direction().short_name(),
+            if self.game.normal_mode { "Normal" } else { "Strict" }
+        ));
+
+        let state = if console.paused_at.is_some() {
+            "Paused".to_string()
+        } else {
+            match self.game.phase() {
+                GamePhase::NotStarted if console.aborted => "Session aborted".to_string(),
+                GamePhase::NotStarted => "Waiting to start".to_string(),
+                GamePhase::ShowingSequence => format!(
+                    "Showing item {} of {}",
+                    (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                    self.game.sequence().len()
+                ),
+                GamePhase::Inputting | GamePhase::ProbeRecall | GamePhase::Recognition => "Answering".to_string(),
+                GamePhase::Success => "Correct".to_string(),
+                GamePhase::GameOver => "Incorrect".to_string(),
+            }
+        };
+        ui.label(format!("State: {}", state));
+        if !self.game.sequence().is_empty() {
+            let sequence: Vec<String> = self.game.sequence().iter().map(|c| c.as_char().to_string()).collect();
+            ui.label(format!("Sequence: {}", sequence.join(" ")));
+        }
+
+        ui.separator();
+        ui.strong("Keystrokes");
+        if self.game.keystrokes().is_empty() {
+            ui.label("None yet");
+        } else {
+            egui::Grid::new("console_keystrokes").num_columns(2).striped(true).show(ui, |ui| {
+                for keystroke in self.game.keystrokes() {
+                    ui.label(format!("{:.3} s", keystroke.ms as f32 / 1000.0));
+                    ui.label(match &keystroke.action {
+                        Action::Item(item) => item.as_char().to_string(),
+                        Action::Delete => "Delete".to_string(),
+                        Action::Match => "Match".to_string(),
+                        Action::DualMatch(stream) => format!("{:?} match", stream),
+                        Action::Equation(answer) => format!("Equation: {}", answer),
+                        Action::Recognition(same) => if *same { "Same" } else { "Different" }.to_string(),
+                    });
+                    ui.end_row();
+                }
+            });
+        }
+
+        ui.separator();
+        ui.strong("Session");
+        match console.accuracy() {
+            Some(accuracy) => ui.label(format!(
+                "{} of {} correct ({:.0}%), {} skipped",
+                console.trials.iter().filter(|trial| trial.success).count(),
+                console.trials.len(),
+                accuracy * 100.0,
+                console.skipped
+            )),
+            None => ui.label(format!("No finished trials yet, {} skipped", console.skipped)),
+        };
+        if let Some(millis) = console.mean_input_ms() {
+            ui.label(format!("Mean answer time: {:.2} s", millis / 1000.0));
+        }
+        if !console.trials.is_empty() {
+            egui::Grid::new("console_trials").num_columns(4).striped(true).show(ui, |ui| {
+                ui.strong("Trial");
+                ui.strong("Length");
+                ui.strong("Result");
+                ui.strong("Time");
+                ui.end_row();
+                for trial in console.trials.iter().rev() {
+                    ui.label(format!("{}", trial.number));
+                    ui.label(format!("{}", trial.length));
+                    if trial.success {
+                        ui.colored_label(egui::Color32::GREEN, format!("correct, {}", trial.score));
+                    } else {
+                        ui.colored_label(egui::Color32::RED, format!("wrong, {}", trial.score));
+                    }
+                    match trial.input_duration_ms {
+                        Some(millis) => ui.label(format!("{:.2} s", millis as f32 / 1000.0)),
+                        None => ui.label("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+        }
+
+        ui.separator();
+        let paused = console.paused_at.is_some();
+        ui.horizontal(|ui| {
+            if self.game.session_setup().is_some() {
+                ui.label("Waiting for the session setup");
+            } else if !self.game.in_round() {
+                if ui.button("Start trial").clicked() {
+                    self.game.start_trial();
+                }
+            } else {
+                if paused && ui.button("Resume").clicked() {
+                    self.game.resume_trial();
+                }
+                if !paused && ui.button("Pause").clicked() {
+                    self.game.pause_trial();
+                }
+                if ui.button("Skip").clicked() {
+                    self.game.skip_trial();
+                }
+                if ui.button("Abort").clicked() {
+                    self.game.abort_session();
+                }
+            }
+            if ui.button("Close console").clicked() {
+                self.game.close_experimenter_console();
+            }
+        });
+    }
+
+    fn show_leaderboard_screen(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Leaderboard");
+
+        if self.game.leaderboard_server.is_some() {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.show_server_rankings, false, "This computer");
+                ui.radio_value(&mut self.show_server_rankings, true, "Server");
+                if self.show_server_rankings && ui.button("Refresh").clicked() {
+                    self.server_rankings = None;
+                }
+            });
+        }
+
+        let mut categories = self.game.leaderboard.categories();
+        if let Some((_, Some(Ok(ranking)))) = &self.server_rankings {
+            categories.extend(ranking.iter().map(Category::of));
+        }
+        let filter = &mut self.leaderboard_filter;
+
+        // Only offer choices that some round has been played with
+        let mut game_types: Vec<(GameMode, Option<TestVariant>, String)> = vec![];
+        let mut lengths: Vec<usize> = vec![];
+        for category in &categories {
+            if !game_types.iter().any(|(mode, variant, _)| (*mode, *variant) == (category.game_mode, category.test_variant)) {
+                game_types.push((category.game_mode, category.test_variant, category.game_type_label()));
+            }
+            if !lengths.contains(&category.length) {
+                lengths.push(category.length);
+            }
+        }
+        lengths.sort();
+
+        ui.horizontal(|ui| {
+            let selected = filter
+                .game_type
+                .and_then(|game_type| game_types.iter().find(|(mode, variant, _)| (*mode, *variant) == game_type))
+                .map_or("Any game", |(_, _, label)| label.as_str());
+            egui::ComboBox::from_id_salt("leaderboard_game_type")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.game_type, None, "Any game");
+                    for (mode, variant, label) in &game_types {
+                        ui.selectable_value(&mut filter.game_type, Some((*mode, *variant)), label);
+                    }
+                });
+
+            egui::ComboBox::from_id_salt("leaderboard_strict")
+                .selected_text(match filter.strict {
+                    None => "Normal or Strict",
+                    Some(false) => "Normal",
+                    Some(true) => "Strict",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.strict, None, "Normal or Strict");
+                    ui.selectable_value(&mut filter.strict, Some(false), "Normal");
+                    ui.selectable_value(&mut filter.strict, Some(true), "Strict");
+                });
+
+            egui::ComboBox::from_id_salt("leaderboard_length")
+                .selected_text(filter.length.map_or("Any length".to_string(), |length| format!("{} items", length)))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.length, None, "Any length");
+                    for length in &lengths {
+                        ui.selectable_value(&mut filter.length, Some(*length), format!("{} items", length));
+                    }
+                });
+
+            egui::ComboBox::from_id_salt("leaderboard_direction")
+                .selected_text(filter.direction.as_ref().map_or("Any direction", |direction| direction.short_name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.direction, None, "Any direction");
+                    for direction in InputDirection::ALL {
+                        let name = direction.short_name();
+                        ui.selectable_value(&mut filter.direction, Some(direction), name);
+                    }
+                });
+        });
+
+        if self.show_server_rankings {
+            let Some(address) = self.game.leaderboard_server.clone() else {
+                return;
+            };
+            // Ask again whenever the filter changes
+            if self.server_rankings.as_ref().is_none_or(|(filter, _)| *filter != self.leaderboard_filter) {
+                self.server_rankings = Some((self.leaderboard_filter.clone(), None));
+                fetch_in_background(address, self.leaderboard_filter.clone(), self.server_replies.clone());
+            }
+            match &self.server_rankings {
+                Some((_, Some(Ok(ranking)))) => show_ranking(ui, &ranking.iter().collect::<Vec<_>>()),
+                Some((_, Some(Err(e)))) => {
+                    ui.colored_label(egui::Color32::YELLOW, format!("Could not fetch the rankings: {}", e));
+                }
+                _ => {
+                    ui.label("Waiting for the server...");
+                }
+            }
+        } else {
+            show_ranking(ui, &self.game.leaderboard.ranking(&self.leaderboard_filter));
+        }
+
+        ui.add_space(10.0);
+        if ui.button("Back").clicked() {
+            self.show_leaderboard = false;
+        }
+    }
+
+    fn show_analytics_screen(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Analytics");
+        ui.label(format!("{} rounds played by {}", self.game.history().len(), self.game.profile.as_deref().unwrap_or("Guest")));
+
+        let charts = all_charts(self.game.history());
+        egui::ScrollArea::vertical().max_height(ui.available_height() - 80.0).show(ui, |ui| {
+            for chart in &charts {
+                ui.add_space(10.0);
+                ui.strong(chart.title);
+                if chart.is_empty() {
+                    ui.label("No rounds to show yet");
+                    continue;
+                }
+                show_chart(ui, chart);
+                if ui.small_button("Copy CSV").clicked() {
+                    ui.output_mut(|o| o.copied_text = chart.to_csv());
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+        if let Some(note) = &self.analytics_note {
+            ui.label(note);
+        }
+        ui.horizontal(|ui| {
+            if let (Some(store), Some(profile)) = (&self.game.profiles, &self.game.profile)
+                && ui.button("Export CSV").clicked() {
+                let dir = store.analytics_dir(profile);
+                self.analytics_note = Some(match export(&charts, &dir) {
+                    Ok(()) => format!("Saved to {}", dir.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                });
+            }
+            if ui.button("Back").clicked() {
+                self.show_analytics = false;
+            }
+        });
+    }
+
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                // Changing settings mid-round would upset the presentation, a challenge brings its own
+                ui.add_enabled_ui(!self.game.in_round() && !self.settings_locked(), |ui| {
+                    egui::Grid::new("settings_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Mode:");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.game.normal_mode, true, "Normal");
+                            ui.radio_value(&mut self.game.normal_mode, false, "Strict");
+                        });
+                        ui.end_row();
+
+                        ui.label("Game type:");
+                        egui::ComboBox::from_id_salt("settings_game_mode")
+                            .selected_text(self.game.game_mode.short_name())
+                            .show_ui(ui, |ui| {
+                                for mode in GameMode::ALL {
+                                    ui.selectable_value(&mut self.game.game_mode, mode, mode.short_name());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Sequence length:");
+                        ui.add(egui::Slider::new(&mut self.game.sequence_length, 1..=12));
+                        ui.end_row();
+
+                        ui.label("Item display time:");
+                        let mut millis = (self.game.item_display_secs * 1000.0).round() as u32;
+                        if ui.add(egui::Slider::new(&mut millis, 200..=3000).suffix(" ms")).changed() {
+                            self.game.item_display_secs = millis as f32 / 1000.0;
+                        }
+                        ui.end_row();
+
+                        ui.label("Direction:");
+                        egui::ComboBox::from_id_salt("settings_direction_policy")
+                            .selected_text(self.game.direction_policy.short_name())
+                            .show_ui(ui, |ui| {
+                                for policy in DirectionPolicy::ALL {
+                                    if policy == DirectionPolicy::Sequencing && !self.game.game_mode.has_sequencing() {
+                                        continue;
+                                    }
+                                    ui.selectable_value(&mut self.game.direction_policy, policy, policy.short_name());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Alphabet:");
+                        ui.horizontal(|ui| {
+                            for alphabet in [Alphabet::Mixed, Alphabet::Digits, Alphabet::Letters] {
+                                let name = alphabet.name();
+                                ui.radio_value(&mut self.game.alphabet, alphabet, name);
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Custom alphabet:");
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.custom_alphabet_text);
+                            if ui.button("Use").clicked() {
+                                match Alphabet::parse(&self.custom_alphabet_text) {
+                                    Ok(alphabet) => self.game.alphabet = alphabet,
+                                    Err(e) => self.game.warning = Some(format!("Custom alphabet: {}", e)),
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                    ui.label(format!("Current alphabet: {}", self.game.alphabet.name()));
+
+                    ui.horizontal(|ui| {
+                        ui.label("Leaderboard server:");
+                        ui.text_edit_singleline(&mut self.server_text).on_hover_text("host:port, empty for none");
+                        if ui.button("Use").clicked() {
+                            let address = self.server_text.trim();
+                            self.game.leaderboard_server = (!address.is_empty()).then(|| address.to_string());
+                            self.server_rankings = None;
+                        }
+                    });
+                    ui.label(format!(
+                        "Current server: {}",
+                        self.game.leaderboard_server.as_deref().unwrap_or("none")
+                    ));
+
+                    ui.separator();
+
+                    if ui.button("Reset to defaults").clicked() {
+                        Settings::default().apply(&mut self.game);
+                    }
+                });
+
+                match &self.game.settings_path {
+                    Some(path) => ui.label(format!("Saved to {}", path.display())),
+                    None => ui.label("No config directory, settings are not saved"),
+                };
+            });
+        self.show_settings = open;
+    }
+
+    fn show_test_variant_result(&self, ui: &mut egui::Ui) {
+        if matches!(self.game.phase(), GamePhase::Success) {
+            ui.heading("Correct!");
+        } else {
+            ui.heading("Game Over!");
+            if let Some(error) = self.game.error_message() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        }
+
+        ui.label("The sequence was:");
+        let correct_seq: String = self.game.sequence()
+            .iter()
+            .map(|c| c.as_char().to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        ui.heading(&correct_seq);
+
+        if let Some(duration) = self.game.input_duration() {
+            ui.label(format!("Time taken: {:.2} seconds", duration.as_secs_f32()));
+        }
+
+        ui.separator();
+
+        match self.game.test_variant {
+            TestVariant::ProbeRecall => {
+                if let Some(answer) = self.game.probe_answer() {
+                    ui.label(format!("Item #{}: you answered '{}'", self.game.probe_index() + 1, answer.as_char()));
+                }
+                ui.label(format!(
+                    "Probe recall this session: {}/{} correct",
+                    self.game.test_scores().probe_correct,
+                    self.game.test_scores().probe_total
+                ));
+            }
+            TestVariant::Recognition => {
+                if let Some(answered_same) = self.game.recognition_answer() {
+                    ui.label(format!("You answered: {}", if answered_same { "Same" } else { "Different" }));
+                }
+                if let Some(foil) = self.game.recognition_probe().foil {
+                    ui.label(format!("The test sequence was a foil: {}", foil.description()));
+                }
+
+                let recognition = &self.game.test_scores().recognition;
+                ui.label(format!(
+                    "Recognition this session: {} hits, {} misses, {} false alarms, {} correct rejections (d′ {:.2})",
+                    recognition.hits,
+                    recognition.misses,
+                    recognition.false_alarms,
+                    recognition.correct_rejections,
+                    recognition.d_prime()
+                ));
+            }
+            TestVariant::SerialRecall => {}
+        }
+    }
+
+    fn show_complex_span_scores(&self, ui: &mut egui::Ui) {
+        let recalled = self.game.correct_positions().iter().filter(|correct| **correct).count();
+        ui.label(format!("Recall: {}/{} items in the correct position", recalled, self.game.sequence().len()));
+
+        let accuracy = self.game.complex_span.accuracy();
+        let color = if self.game.complex_span.meets_threshold() {
+            egui::Color32::GREEN
+        } else {
+            egui::Color32::RED
+        };
+        ui.colored_label(color, format!(
+            "Equations: {}/{} correct ({:.0}%, threshold {:.0}%)",
+            self.game.complex_span.correct_answers(),
+            self.game.complex_span.equation_count(),
+            accuracy * 100.0,
+            self.game.complex_span.accuracy_threshold * 100.0
+        ));
+    }
+}
+
+impl eframe::App for MemoryGameApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Handle keyboard input
+        self.handle_key_input(ctx);
+
+        let previous_game_type = (self.game.game_mode, self.game.test_variant);
+        // Filled in by whichever screen draws items this frame
+        self.visible_items.clear();
+
+        if self.game.watching() {
+            // The watched game sets the pace
+            self.game.poll_viewer();
+        } else if !self.game.is_paused() {
+            // Update the sequence display timer (only during showing sequence phase)
+            self.game.advance(ctx.input(|i| i.stable_dt));
+        }
+        // Before the round is saved, so an opponent's earlier finish is seen first
+        self.game.poll_race();
+        self.save_finished_round();
+        self.take_server_replies();
+
+        // Notes on the last round go once the next one is under way
+        if self.game.in_round() {
+            self.record_note = None;
+            self.server_note = None;
+            self.daily_share = None;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(20.0);
+
+                if self.game.session_setup().is_some() {
+                    self.show_session_setup(ui);
+                    return;
+                }
+
+                if self.quest_setup.is_some() {
+                    self.show_quest_setup(ui);
+                    return;
+                }
+
+                // The participant only sees the trials while the experimenter runs the session
+                if self.game.experimenter().is_none() {
+                    ui.heading("Memory Game");
+
+                    // Mode selection - Normal mode first
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(!self.settings_locked(), |ui| {
+                            ui.radio_value(&mut self.game.normal_mode, true, "Normal");
+                            ui.radio_value(&mut self.game.normal_mode, false, "Strict");
+                        });
+
+                        ui.separator();
+                        ui.label(format!("Player: {}", self.game.profile.as_deref().unwrap_or("Guest")));
+
+                        if let Some(session) = self.game.session() {
+                            ui.separator();
+                            ui.label(format!("Participant {}, session {}", session.participant_id, session.session));
+                        }
+                    });
+
+                    // Game mode can only be changed between rounds, and isn't up to the player in a challenge
+                    let in_round = self.game.in_round();
+                    ui.add_enabled_ui(!in_round && !self.settings_locked(), |ui| {
+                        ui.horizontal(|ui| {
+                            for mode in GameMode::ALL {
+                                ui.radio_value(&mut self.game.game_mode, mode, mode.short_name());
+                            }
+                        });
+
+                        if self.game.game_mode == GameMode::Sequence {
+                            ui.horizontal(|ui| {
+                                for variant in TestVariant::ALL {
+                                    ui.radio_value(&mut self.game.test_variant, variant, variant.short_name());
+                                }
+                            });
+                        }
+                    });
+
+                    if let Some(warning) = &self.game.warning {
+                        ui.colored_label(egui::Color32::YELLOW, warning);
+                    }
+
+                    if let Some(challenge) = self.game.challenge() {
+                        let name = match challenge.daily {
+                            Some(day) => format!("Daily Challenge {}", daily_date(day)),
+                            None => "Challenge".to_string(),
+                        };
+                        ui.label(format!("{} - Start New Game goes back to your own settings", name));
+                    }
+
+                    if let Some(broadcast) = self.game.broadcast() {
+                        ui.label(format!("Broadcasting on {} ({} watching)", broadcast.address, broadcast.viewer_count()));
+                    }
+                    if let Some(event_feed) = self.game.event_feed() {
+                        ui.label(format!("Event feed on ws://{} ({} connected)", event_feed.address, event_feed.client_count()));
+                    }
+
+                    ui.separator();
+                }
+
+                if let Some(viewer) = self.game.viewer() {
+                    let player = viewer.player.as_deref().unwrap_or("the player");
+                    match &viewer.status {
+                        ViewerStatus::Connecting => {
+                            ui.heading(format!("Connecting to {}...", viewer.address));
+                            return;
+                        }
+                        ViewerStatus::Disconnected(reason) => {
+                            ui.heading("No longer watching");
+                            ui.label(reason);
+                            return;
+                        }
+                        ViewerStatus::Watching => {
+                            ui.label(format!("Watching {} on {}", player, viewer.address));
+                        }
+                    }
+                    if matches!(self.game.phase(), GamePhase::NotStarted) {
+                        ui.heading(format!("Waiting for {} to start a round", player));
+                        return;
+                    }
+                }
+
+                if self.game.show_profiles {
+                    self.show_profile_screen(ui);
+                    return;
+                }
+
+                if self.show_leaderboard {
+                    self.show_leaderboard_screen(ui);
+                    return;
+                }
+
+                if self.show_analytics {
+                    self.show_analytics_screen(ui);
+                    return;
+                }
+
+                if self.hot_seat_setup.is_some() {
+                    self.show_hot_seat_setup(ui);
+                    return;
+                }
+
+                if let Some(hot_seat) = self.game.hot_seat() {
+                    if hot_seat.stage != HotSeatStage::Playing {
+                        self.show_hot_seat_screen(ui);
+                        return;
+                    }
+                    ui.label(format!("{}'s turn, round {}", hot_seat.current_player().name, hot_seat.round()));
+                }
+
+                if self.show_race_setup {
+                    self.show_race_setup(ui);
+                    return;
+                }
+
+                if let Some(race) = self.game.race() {
+                    if race.stage != RaceStage::Racing {
+                        self.show_race_screen(ui);
+                        return;
+                    }
+                    // Only how far the opponent is, never what they entered
+                    let opponent = race.opponent_name.as_deref().unwrap_or("Opponent");
+                    match race.opponent_finished {
+                        Some(_) => ui.label(format!("{} has finished", opponent)),
+                        None => ui.label(format!("{}: {} of {} entered", opponent, race.opponent_progress, self.game.sequence().len())),
+                    };
+                    if race.waiting_for_opponent(&self.game) {
+                        ui.label(format!("Waiting for {}...", opponent));
+                    }
+                }
+
+                if let Some(console) = self.game.experimenter() {
+                    if console.paused_at.is_some() {
+                        ui.heading("Paused");
+                        return;
+                    }
+                    if !self.game.in_round() {
+                        ui.heading(if console.aborted { "The session has ended" } else { "Please wait" });
+                        return;
+                    }
+                }
+
+                if let Some(digit_span) = self.game.digit_span() {
+                    if digit_span.stage != DigitSpanStage::Running && !self.game.in_round() {
+                        self.show_digit_span_screen(ui);
+                        return;
+                    }
+                    if let Some(direction) = digit_span.direction() {
+                        ui.label(format!(
+                            "{}, {} digits, trial {} of {}",
+                            direction.short_name(),
+                            digit_span.length,
+                            digit_span.trial + 1,
+                            digit_span.protocol.trials_per_length
+                        ));
+                    }
+                }
+
+                if let Some(quest) = self.game.quest() {
+                    if quest.stage == QuestStage::Finished && !self.game.in_round() {
+                        self.show_quest_result(ui);
+                        return;
+                    }
+                    let (low, high) = quest.credible_interval();
+                    ui.label(format!(
+                        "Threshold trial {}: {}, estimate {} ({} to {})",
+                        quest.trials.len() + 1,
+                        quest.parameter.format(quest.current),
+                        quest.parameter.format(quest.estimate()),
+                        quest.parameter.format(low),
+                        quest.parameter.format(high)
+                    ));
+                }
+
+                if let Some(study) = self.game.study() {
+                    if study.stage != StudyStage::Running && !self.game.in_round() {
+                        self.show_study_screen(ui);
+                        return;
+                    }
+                    ui.label(format!(
+                        "Block {} of {}, trial {} of {}",
+                        study.block + 1,
+                        study.block_count(),
+                        study.trials_done + 1,
+                        study.design.trials_per_block
+                    ));
+                }
+
+                match self.game.phase() {
+                    GamePhase::NotStarted => {
+                        ui.heading("Click 'Start New Game' to begin!");
+
+                        ui.label("Instructions:");
+                        ui.label("1. Remember the sequence of numbers and letters shown on screen");
+                        ui.label("2. Enter them in the required order when prompted");
+                        ui.label("3. Use Backspace/Delete to remove the last entry if you make a mistake");
+                        ui.label("In Spatial Span mode, remember the order the blocks light up and click them");
+
+                        if self.game.game_mode == GameMode::NBack {
+                            ui.separator();
+                            ui.label("N-Back settings:");
+                            ui.add(egui::Slider::new(&mut self.game.n_back.n, 1..=5).text("N"));
+                            ui.add(egui::Slider::new(&mut self.game.n_back.stream_length, 10..=MAX_STREAM_LENGTH).text("Stream length"));
+                            ui.add(egui::Slider::new(&mut self.game.n_back.target_rate, 0.1..=0.5).text("Target rate"));
+                        }
+
+                        if self.game.game_mode == GameMode::DualNBack {
+                            ui.separator();
+                            ui.label("Dual N-Back settings:");
+                            ui.add(egui::Slider::new(&mut self.game.dual_n_back.n, 1..=9).text("N"));
+                            ui.add(egui::Slider::new(&mut self.game.dual_n_back.stream_length, 10..=MAX_STREAM_LENGTH).text("Stream length"));
+                            ui.add(egui::Slider::new(&mut self.game.dual_n_back.target_rate, 0.1..=0.5).text("Target rate"));
+                            ui.checkbox(&mut self.game.dual_n_back.adaptive, "Adapt N to block accuracy");
+                        }
+
+                        if self.game.game_mode == GameMode::ComplexSpan {
+                            ui.separator();
+                            ui.label("Complex Span settings:");
+                            ui.add(
+                                egui::Slider::new(&mut self.game.complex_span.accuracy_threshold, ACCURACY_THRESHOLDS)
+                                    .text("Equation accuracy threshold")
+                            );
+                        }
+                    }
+
+                    GamePhase::ShowingSequence if self.game.game_mode == GameMode::SpatialSpan => {
+                        ui.heading("Watch the blocks:");
+
+                        // Light up only the block for the current step
+                        let highlighted = match self.game.sequence().get(self.game.current_sequence_index()) {
+                            Some(CharType::Block(b)) => Some(*b as usize),
+                            _ => None,
+                        };
+                        show_block_grid(ui, self.game.block_grid(), highlighted, false, false);
+                        if highlighted.is_some() {
+                            self.visible_items = vec![self.game.current_sequence_index()];
+                        }
+
+                        ui.label(format!(
+                            "Showing sequence... {}/{}",
+                            (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                            self.game.sequence().len()
+                        ));
+                    }
+
+                    GamePhase::ShowingSequence if self.game.game_mode == GameMode::NBack => {
+                        ui.heading(format!("Press Space when the item matches the one {} back", self.game.n_back.block_n()));
+
+                        // Blank out the end of each slot so repeated items are seen as separate
+                        let slot_progress = (self.game.sequence_display_timer() / self.game.item_display_secs).fract();
+                        let item_text = match self.game.sequence().get(self.game.current_sequence_index()) {
+                            Some(item) if slot_progress < 0.75 => item.as_char().to_string(),
+                            _ => String::new(),
+                        };
+                        if !item_text.is_empty() {
+                            self.visible_items = vec![self.game.current_sequence_index()];
+                        }
+                        ui.heading(egui::RichText::new(item_text).size(64.0));
+
+                        if self.game.n_back.has_responded(self.game.current_sequence_index()) {
+                            ui.label("Match response recorded");
+                        } else {
+                            ui.label("");
+                        }
+
+                        ui.label(format!(
+                            "Item {}/{}",
+                            (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                            self.game.sequence().len()
+                        ));
+                    }
+
+                    GamePhase::ShowingSequence if self.game.game_mode == GameMode::DualNBack => {
+                        ui.heading(format!("{}-Back: A = position match, L = character match", self.game.dual_n_back.block_n()));
+
+                        let slot_progress = (self.game.sequence_display_timer() / self.game.item_display_secs).fract();
+                        let item = self.game.sequence()
+                            .get(self.game.current_sequence_index())
+                            .filter(|_| slot_progress < 0.75);
+                        if item.is_some() {
+                            self.visible_items = vec![self.game.current_sequence_index()];
+                        }
+                        show_dual_n_back_board(ui, &self.game.dual_n_back, self.game.current_sequence_index(), item);
+
+                        ui.horizontal(|ui| {
+                            for (stream, label) in [(MatchStream::Position, "Position"), (MatchStream::Character, "Character")] {
+                                if self.game.dual_n_back.has_responded(stream, self.game.current_sequence_index()) {
+                                    ui.label(format!("{} match recorded", label));
+                                }
+                            }
+                        });
+
+                        ui.label(format!(
+                            "Item {}/{}",
+                            (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                            self.game.sequence().len()
+                        ));
+                    }
+
+                    GamePhase::ShowingSequence if self.game.game_mode == GameMode::ComplexSpan => {
+                        if let Some(equation) = self.game.complex_span.pending_equation(self.game.current_sequence_index()) {
+                            ui.heading("Is this equation correct?");
+                            ui.heading(egui::RichText::new(equation.text()).size(40.0));
+
+                            ui.horizontal(|ui| {
+                                if ui.button("True (T)").clicked() && !self.game.watching() {
+                                    self.game.answer_equation(true);
+                                }
+                                if ui.button("False (F)").clicked() && !self.game.watching() {
+                                    self.game.answer_equation(false);
+                                }
+                            });
+                        } else {
+                            ui.heading("Remember the item:");
+
+                            // Only the current item is on screen, one at a time
+                            let item_text = self.game.sequence()
+                                .get(self.game.current_sequence_index())
+                                .map(|c| c.as_char().to_string())
+                                .unwrap_or_default();
+                            if !item_text.is_empty() {
+                                self.visible_items = vec![self.game.current_sequence_index()];
+                            }
+                            ui.heading(egui::RichText::new(item_text).size(64.0));
+                        }
+
+                        ui.label(format!(
+                            "Showing sequence... {}/{}",
+                            (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                            self.game.sequence().len()
+                        ));
+                    }
+
+                    GamePhase::ShowingSequence => {
+                        ui.heading("Remember the sequence:");
+
+                        // Show only up to current_sequence_index
+                        let mut seq_text = String::new();
+                        for i in 0..=self.game.current_sequence_index().min(self.game.sequence().len()) {
+                            if i < self.game.sequence().len() {
+                                seq_text.push_str(&format!("{} ", self.game.sequence()[i].as_char()));
+                                self.visible_items.push(i);
+                            }
+                        }
+
+                        ui.heading(&seq_text);
+
+                        // Progress indicator
+                        ui.label(format!(
+                            "Showing sequence... {}/{}",
+                            (self.game.current_sequence_index() + 1).min(self.game.sequence().len()),
+                            self.game.sequence().len()
+                        ));
+                    }
+
+                    GamePhase::Inputting if self.game.game_mode == GameMode::SpatialSpan => {
+                        ui.heading("Click the blocks:");
+
+                        ui.horizontal(|ui| {
+                            ui.label("Direction: ");
+                            ui.colored_label(
+                                direction_color(self.game.input_direction()),
+                                self.game.input_direction().short_name().to_string()
+                            );
+                        });
+
+                        if let Some(block) = show_block_grid(ui, self.game.block_grid(), None, !self.game.watching(), false) {
+                            self.game.process_input(CharType::Block(block as u8));
+                        }
+
+                        if !self.game.normal_mode
+                            && let Some(error) = self.game.error_message() {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+
+                        ui.label("Press Backspace/Delete to remove the last click");
+
+                        ui.separator();
+
+                        ui.label(format!(
+                            "Blocks remaining: {}",
+                            self.game.sequence().len() - self.game.user_input().len()
+                        ));
+                    }
+
+                    GamePhase::Inputting => {
+                        ui.heading("Enter the sequence:");
+
+                        // Show visual indicator for input direction with enhanced styling
+                        ui.horizontal(|ui| {
+                            ui.label("Direction: ");
+                            ui.colored_label(
+                                direction_color(self.game.input_direction()),
+                                self.game.input_direction().short_name().to_string()
+                            );
+                        });
+
+                        // Show what the user has entered so far
+                        let entered: String = self.game.user_input()
+                            .iter()
+                            .map(|c| c.as_char().to_string())
+                            .collect::<Vec<String>>()
+                            .join(" ");
+
+                        ui.heading(format!("Your input: [{}]", entered));
+
+                        // Only show error in Strict mode during input
+                        if !self.game.normal_mode
+                            && let Some(error) = self.game.error_message() {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+
+                        ui.label("Press the corresponding keys on your keyboard");
+                        ui.label("Press Backspace/Delete to remove the last entry");
+
+                        ui.separator();
+
+                        // Show the remaining count
+                        ui.label(format!(
+                            "Characters remaining: {}",
+                            self.game.sequence().len() - self.game.user_input().len()
+                        ));
+                    }
+
+                    GamePhase::ProbeRecall => {
+                        ui.heading(format!("What was item #{}?", self.game.probe_index() + 1));
+                        ui.label("Press the key for that item");
+                    }
+
+                    GamePhase::Recognition => {
+                        ui.heading("Is this the sequence you saw?");
+
+                        let candidate: String = self.game.recognition_probe().candidate
+                            .iter()
+                            .map(|c| c.as_char().to_string())
+                            .collect::<Vec<String>>()
+                            .join(" ");
+                        ui.heading(&candidate);
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Same (Y)").clicked() && !self.game.watching() {
+                                self.game.answer_recognition(true);
+                            }
+                            if ui.button("Different (N)").clicked() && !self.game.watching() {
+                                self.game.answer_recognition(false);
+                            }
+                        });
+                    }
+
+                    GamePhase::GameOver | GamePhase::Success
+                        if self.game.game_mode == GameMode::Sequence && self.game.test_variant != TestVariant::SerialRecall => {
+                        self.show_test_variant_result(ui);
+                    }
+
+                    GamePhase::GameOver => {
+                        ui.heading("Game Over!");
+
+                        if let Some(error) = self.game.error_message() {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+
+                        // Show both sequences for comparison
+                        ui.label("Your input:");
+                        let user_seq: String = self.game.user_input()
+                            .iter()
+                            .map(|c| c.as_char().to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ");
+                        ui.heading(&user_seq);
+
+                        // Show the correct sequence based on input direction
+                        let correct_seq: String = self
+                            .game
+                            .expected_input()
+                            .iter()
+                            .map(|c| c.as_char().to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ");
+
+                        ui.horizontal(|ui| {
+                            ui.label("Correct sequence: ");
+                            ui.colored_label(
+                                direction_color(self.game.input_direction()),
+                                self.game.input_direction().short_name().to_string()
+                            );
+                        });
+                        ui.heading(&correct_seq);
+
+                        if self.game.game_mode == GameMode::SpatialSpan {
+                            show_block_grid(ui, self.game.block_grid(), None, false, true);
+                        }
+
+                        if self.game.game_mode == GameMode::ComplexSpan {
+                            self.show_complex_span_scores(ui);
+                        }
+
+                        // Show input time if available
+                        if let Some(duration) = self.game.input_duration() {
+                            let seconds = duration.as_secs_f32();
+                            ui.label(format!("Time taken: {:.2} seconds", seconds));
+                        }
+
+                        if self.game.normal_mode {
+                            ui.label("In Normal mode, the sequence was checked after you entered all characters.");
+                        }
+                    }
+
+                    GamePhase::Success if self.game.game_mode == GameMode::NBack => {
+                        ui.heading(format!("{}-Back block complete!", self.game.n_back.block_n()));
+
+                        let results = self.game.n_back.results(self.game.sequence());
+                        egui::Grid::new("n_back_results").show(ui, |ui| {
+                            ui.label("Hits:");
+                            ui.label(results.hits.to_string());
+                            ui.end_row();
+                            ui.label("Misses:");
+                            ui.label(results.misses.to_string());
+                            ui.end_row();
+                            ui.label("False alarms:");
+                            ui.label(results.false_alarms.to_string());
+                            ui.end_row();
+                            ui.label("Correct rejections:");
+                            ui.label(results.correct_rejections.to_string());
+                            ui.end_row();
+                            ui.label("d′:");
+                            ui.label(format!("{:.2}", results.d_prime()));
+                            ui.end_row();
+                        });
+                    }
+
+                    GamePhase::Success if self.game.game_mode == GameMode::DualNBack => {
+                        ui.heading(format!("Dual {}-Back block complete!", self.game.dual_n_back.block_n()));
+
+                        let position = self.game.dual_n_back.results(MatchStream::Position, self.game.sequence());
+                        let character = self.game.dual_n_back.results(MatchStream::Character, self.game.sequence());
+                        egui::Grid::new("dual_n_back_results").show(ui, |ui| {
+                            ui.label("");
+                            ui.label("Position");
+                            ui.label("Character");
+                            ui.end_row();
+                            for (label, position_value, character_value) in [
+                                ("Hits:", position.hits.to_string(), character.hits.to_string()),
+                                ("Misses:", position.misses.to_string(), character.misses.to_string()),
+                                ("False alarms:", position.false_alarms.to_string(), character.false_alarms.to_string()),
+                                ("Correct rejections:", position.correct_rejections.to_string(), character.correct_rejections.to_string()),
+                                ("Accuracy:", format!("{:.0}%", position.accuracy() * 100.0), format!("{:.0}%", character.accuracy() * 100.0)),
+                                ("d′:", format!("{:.2}", position.d_prime()), format!("{:.2}", character.d_prime())),
+                            ] {
+                                ui.label(label);
+                                ui.label(position_value);
+                                ui.label(character_value);
+                                ui.end_row();
+                            }
+                        });
+
+                        if self.game.dual_n_back.adaptive {
+                            let next_n = self.game.dual_n_back.n;
+                            let change = match next_n.cmp(&self.game.dual_n_back.block_n()) {
+                                std::cmp::Ordering::Greater => "raised",
+                                std::cmp::Ordering::Less => "lowered",
+                                std::cmp::Ordering::Equal => "kept",
+                            };
+                            ui.label(format!("N {} to {} for the next block", change, next_n));
+                        }
+                    }
+
+                    GamePhase::Success => {
+                        ui.horizontal(|ui| {
+                            ui.heading("Congratulations!");
+                        });
+
+                        ui.colored_label(egui::Color32::GREEN,
+                            format!("You remembered the sequence correctly in {}!",
+                                    self.game.input_direction().short_name())
+                        );
+
+                        // Show the sequence for confirmation
+                        ui.label("The sequence was:");
+                        let correct_seq: String = self.game.sequence()
+                            .iter()
+                            .map(|c| c.as_char().to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ");
+                        ui.heading(&correct_seq);
+
+                        if self.game.game_mode == GameMode::SpatialSpan {
+                            show_block_grid(ui, self.game.block_grid(), None, false, true);
+                        }
+
+                        if self.game.game_mode == GameMode::ComplexSpan {
+                            self.show_complex_span_scores(ui);
+                        }
+
+                        // Show input time if available
+                        if let Some(duration) = self.game.input_duration() {
+                            let seconds = duration.as_secs_f32();
+                            ui.label(format!("Time taken: {:.2} seconds", seconds));
+                        }
+
+                        ui.label(format!("Score: {}", self.game.score()));
+                        if let Some(note) = &self.record_note {
+                            ui.colored_label(egui::Color32::GOLD, note);
+                        }
+                        if let Some(note) = &self.server_note {
+                            ui.colored_label(egui::Color32::GOLD, note);
+                        }
+                    }
+                }
+
+                if self.game.experimenter().is_some() {
+                    return;
+                }
+
+                if matches!(self.game.phase(), GamePhase::Success | GamePhase::GameOver)
+                    && let Some(share) = &self.daily_share {
+                    ui.add_space(10.0);
+                    ui.label(share);
+                    if ui.button("Copy result").clicked() {
+                        let share = share.clone();
+                        ui.output_mut(|o| o.copied_text = share);
+                    }
+                }
+
+                if matches!(self.game.phase(), GamePhase::Success | GamePhase::GameOver)
+                    && let Ok(code) = Challenge::from_round(&self.game).and_then(|challenge| challenge.to_code()) {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Challenge code for this round: {}", code));
+                        if ui.button("Copy code").clicked() {
+                            ui.output_mut(|o| o.copied_text = code);
+                        }
+                    });
+                }
+
+                ui.add_space(30.0);
+
+                // A hot seat turn is only ever left by finishing it, and viewers only watch
+                if self.game.hot_seat().is_some() || self.game.watching() {
+                    return;
+                }
+
+                if self.game.race().is_some() {
+                    if ui.button("Leave race").clicked() {
+                        self.game.leave_race();
+                    }
+                    return;
+                }
+
+                if self.game.study().is_some() {
+                    if !self.game.in_round() && ui.button("Next trial").clicked() {
+                        self.game.next_study_trial();
+                    }
+                    return;
+                }
+
+                if let Some(quest) = self.game.quest() {
+                    if !self.game.in_round() {
+                        show_quest_estimate(ui, quest);
+                    }
+                    ui.horizontal(|ui| {
+                        if !self.game.in_round() && ui.button("Next trial").clicked() {
+                            self.game.next_quest_trial();
+                        }
+                        if ui.button("Stop").clicked() {
+                            self.game.leave_quest();
+                        }
+                    });
+                    return;
+                }
+
+                if self.game.digit_span().is_some() {
+                    ui.horizontal(|ui| {
+                        if !self.game.in_round() && ui.button("Next trial").clicked() {
+                            self.game.next_digit_span_trial();
+                        }
+                        if ui.button("Stop battery").clicked() {
+                            self.game.leave_digit_span();
+                        }
+                    });
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Start New Game").clicked() {
+                        self.game.start_game();
+                    }
+
+                    if !self.game.in_round() && ui.button("Daily Challenge").clicked() {
+                        self.game.start_challenge(Challenge::today());
+                    }
+
+                    if !self.game.in_round() && ui.button("Hot Seat").clicked() {
+                        self.hot_seat_setup = Some(HotSeatSetup::default());
+                    }
+
+                    if !self.game.in_round() && ui.button("Race").clicked() {
+                        self.show_race_setup = true;
+                    }
+
+                    if !self.game.in_round() && ui.button("Experimenter").clicked() {
+                        self.game.open_experimenter_console();
+                    }
+
+                    if !self.game.in_round() && ui.button("Session").clicked() {
+                        self.game.open_session_setup();
+                    }
+
+                    if !self.game.in_round() && ui.button("Find Threshold").clicked() {
+                        self.quest_setup = Some(QuestSetup::default());
+                    }
+
+                    if !self.game.in_round() && ui.button("Digit Span").clicked() {
+                        let protocol = match DigitSpanProtocol::path() {
+                            Some(path) => DigitSpanProtocol::load(&path),
+                            None => Ok(DigitSpanProtocol::default()),
+                        };
+                        if let Err(e) = protocol.and_then(|protocol| self.game.start_digit_span(protocol)) {
+                            self.game.warning = Some(format!("Digit span: {}", e));
+                        }
+                    }
+
+                    if ui.button("Settings").clicked() {
+                        self.show_settings = !self.show_settings;
+                    }
+
+                    if self.game.profiles.is_some() && !self.game.in_round() && ui.button("Profiles").clicked() {
+                        self.game.show_profiles = true;
+                    }
+
+                    if self.game.profiles.is_some() && !self.game.in_round() && ui.button("Leaderboard").clicked() {
+                        // Open on the category of the current settings
+                        self.leaderboard_filter = Filter {
+                            game_type: Some((
+                                self.game.game_mode,
+                                (self.game.game_mode == GameMode::Sequence).then_some(self.game.test_variant),
+                            )),
+                            strict: Some(!self.game.normal_mode),
+                            length: None,
+                            direction: None,
+                        };
+                        self.show_leaderboard = true;
+                    }
+
+                    if self.game.profile.is_some() && !self.game.in_round() && ui.button("Analytics").clicked() {
+                        self.analytics_note = None;
+                        self.show_analytics = true;
+                    }
+
+                    // if matches!(self.game.phase(), GamePhase::Inputting) {
+                    //     if ui.button("Delete Last").clicked() {
+                    //         self.game.delete_last();
+                    //     }
+                    // }
+                });
+
+                ui.horizontal(|ui| {
+                    if self.game.in_round() {
+                        return;
+                    }
+                    ui.label("Challenge code:");
+                    ui.text_edit_singleline(&mut self.challenge_code_text);
+                    if ui.button("Play challenge").clicked() {
+                        match Challenge::from_code(&self.challenge_code_text) {
+                            Ok(challenge) => {
+                                self.game.warning = None;
+                                self.game.start_challenge(challenge);
+                            }
+                            Err(e) => self.game.warning = Some(format!("Challenge code: {}", e)),
+                        }
+                    }
+                });
+
+                ui.add_space(20.0);
+
+                ui.collapsing("Personal Bests", |ui| {
+                    self.show_personal_bests(ui);
+                });
+
+                ui.collapsing("Game Instructions", |ui| {
+                    ui.label("1. Remember the sequence of numbers and letters shown on screen");
+                    ui.label("2. Enter them in the required order when prompted");
+                    ui.label("3. Use Backspace or Delete key to remove the last entry if you make a mistake");
+                    ui.label("4. Be quick - you need to remember and enter the sequence correctly!");
+                    ui.separator();
+                    ui.label("Modes:");
+                    ui.label("- Normal: Enter the entire sequence, errors are checked at the end");
+                    ui.label("- Strict: Game ends immediately if you make a mistake");
+                    ui.separator();
+                    ui.label("Game types:");
+                    ui.label("- Sequence: Remember numbers and letters and type them");
+                    ui.label("- Spatial Span: Remember the order blocks light up and click them");
+                    ui.label("- N-Back: Press Space whenever the item matches the one N steps back");
+                    ui.label("- Dual N-Back: Press A when the position matches N steps back, L when the character does");
+                    ui.label("- Complex Span: Verify an equation (T/F) between items, then recall the items");
+                    ui.separator();
+                    ui.label("Sequence tests:");
+                    ui.label("- Serial Recall: Enter the whole sequence");
+                    ui.label("- Probe Recall: Press the key for the item at the asked position");
+                    ui.label("- Recognition: Say whether a shown sequence is the one you saw (Y/N)");
+                });
+            });
+        });
+
+        self.show_settings_window(ctx);
+        self.show_experimenter_console(ctx);
+
+        // Results on screen belong to the previous game type, so start over
+        // (a round that just started, like a challenge, brings its own game type)
+        if (self.game.game_mode, self.game.test_variant) != previous_game_type {
+            self.game.clear_results();
+        }
+
+        // Settings are saved once the pointer lets go of whatever changed them
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.game.save_settings_if_changed();
+        }
+
+        // After the panels, so clicks and buttons handled there go out with this frame
+        self.game.send_round_events();
+        self.game.mark_frame(&self.visible_items);
+
+        // Request repaint for animation
+        ctx.request_repaint();
+    }
+}
+
+// Estimate with its credible interval and the posterior it comes from
+fn show_quest_estimate(ui: &mut egui::Ui, quest: &Quest) {
+    let (low, high) = quest.credible_interval();
+    ui.label(format!(
+        "{} for {:.0}% correct: {} (95% credible interval {} to {})",
+        quest.parameter.name(),
+        quest.target * 100.0,
+        quest.parameter.format(quest.estimate()),
+        quest.parameter.format(low),
+        quest.parameter.format(high)
+    ));
+    let correct = quest.trials.iter().filter(|(_, success)| *success).count();
+    ui.label(format!(
+        "{} trials, {} correct{}",
+        quest.trials.len(),
+        correct,
+        if quest.precise_enough() { ", precise enough to stop" } else { "" }
+    ));
+
+    // Posterior density, with the interval shaded
+    let density = quest.density();
+    let (first, last) = (density[0].0, density[density.len() - 1].0);
+    let (min, max) = (first.min(last), first.max(last));
+    let peak = density.iter().map(|(_, p)| *p).fold(0.0, f64::max);
+    if peak <= 0.0 {
+        return;
+    }
+    let (response, painter) = ui.allocate_painter(egui::vec2(320.0, 90.0), egui::Sense::hover());
+    let rect = response.rect;
+    let x = |value: f64| rect.left() + ((value - min) / (max - min)) as f32 * rect.width();
+    let y = |p: f64| rect.bottom() - (p / peak) as f32 * rect.height();
+    painter.rect_filled(
+        egui::Rect::from_x_y_ranges(x(low)..=x(high), rect.y_range()),
+        0.0,
+        egui::Color32::from_rgba_unmultiplied(100, 150, 255, 40),
+    );
+    let points: Vec<egui::Pos2> = density.iter().map(|(value, p)| egui::pos2(x(*value), y(*p))).collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE)));
+    painter.line_segment([rect.left_bottom(), rect.right_bottom()], egui::Stroke::new(1.0, egui::Color32::GRAY));
+    painter.text(rect.left_bottom(), egui::Align2::LEFT_TOP, quest.parameter.format(min), egui::FontId::default(), egui::Color32::GRAY);
+    painter.text(rect.right_bottom(), egui::Align2::RIGHT_TOP, quest.parameter.format(max), egui::FontId::default(), egui::Color32::GRAY);
+    ui.add_space(16.0);
+}
+
+// Line chart with a line per series, its axis ranges and a legend
+fn show_chart(ui: &mut egui::Ui, chart: &Chart) {
+    let points = chart.series.iter().flat_map(|series| series.points.iter());
+    let (x_min, x_max) = points.clone().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (x, _)| (min.min(*x), max.max(*x)));
+    let y_max = chart.y_max.unwrap_or_else(|| points.map(|(_, y)| *y).fold(0.0, f64::max) * 1.1).max(f64::EPSILON);
+    let x_span = (x_max - x_min).max(1.0);
+
+    let (response, painter) = ui.allocate_painter(egui::vec2(360.0, 120.0), egui::Sense::hover());
+    let rect = response.rect.shrink2(egui::vec2(30.0, 6.0));
+    let x = |value: f64| rect.left() + ((value - x_min) / x_span) as f32 * rect.width();
+    let y = |value: f64| rect.bottom() - (value / y_max) as f32 * rect.height();
+    let axis = egui::Stroke::new(1.0, egui::Color32::GRAY);
+    painter.line_segment([rect.left_bottom(), rect.right_bottom()], axis);
+    painter.line_segment([rect.left_bottom(), rect.left_top()], axis);
+    let font = egui::FontId::proportional(11.0);
+    painter.text(rect.left_top(), egui::Align2::RIGHT_CENTER, format!("{:.1} ", y_max), font.clone(), egui::Color32::GRAY);
+    painter.text(rect.left_bottom(), egui::Align2::RIGHT_CENTER, "0 ", font.clone(), egui::Color32::GRAY);
+
+    for (index, series) in chart.series.iter().enumerate() {
+        let color = SERIES_COLORS[index % SERIES_COLORS.len()];
+        let line: Vec<egui::Pos2> = series.points.iter().map(|(px, py)| egui::pos2(x(*px), y(*py))).collect();
+        for point in &line {
+            painter.circle_filled(*point, 2.5, color);
+        }
+        painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+    }
+
+    // Sessions can be many, so only the first and last are named
+    painter.text(rect.left_bottom(), egui::Align2::LEFT_TOP, chart.x_name(x_min), font.clone(), egui::Color32::GRAY);
+    if x_max > x_min {
+        painter.text(rect.right_bottom(), egui::Align2::RIGHT_TOP, chart.x_name(x_max), font, egui::Color32::GRAY);
+    }
+    ui.add_space(12.0);
+    ui.horizontal(|ui| {
+        ui.label(format!("{} by {}:", chart.y_label, chart.x_label));
+        for (index, series) in chart.series.iter().enumerate() {
+            ui.colored_label(SERIES_COLORS[index % SERIES_COLORS.len()], &series.name);
+        }
+    });
+}
+
+const SERIES_COLORS: [egui::Color32; 3] = [egui::Color32::LIGHT_BLUE, egui::Color32::from_rgb(255, 160, 80), egui::Color32::LIGHT_GREEN];
+
+fn show_digit_span_scores(ui: &mut egui::Ui, digit_span: &DigitSpan, norms: Option<&Norms>, age: Option<u32>) {
+    if digit_span.scores.is_empty() {
+        ui.label("No trials yet");
+        return;
+    }
+    // Spans and totals come in pairs per direction, then the battery total
+    let measures = digit_span_measures(digit_span, norms, age);
+    egui::Grid::new("digit_span_scores").num_columns(4).striped(true).show(ui, |ui| {
+        ui.strong("Direction");
+        ui.strong("Longest span");
+        ui.strong("Raw total");
+        ui.strong("Trials");
+        ui.end_row();
+        for (score, pair) in digit_span.scores.iter().zip(measures.chunks(2)) {
+            ui.label(score.direction.short_name());
+            ui.label(normed_text(&pair[0]));
+            ui.label(normed_text(&pair[1]));
+            let trials: Vec<String> = score
+                .trials
+                .iter()
+                .map(|(length, success)| format!("{}{}", length, if *success { "+" } else { "-" }))
+                .collect();
+            ui.label(trials.join(" "));
+            ui.end_row();
+        }
+    });
+    if let Some(total) = measures.last() {
+        ui.label(format!("Total raw score: {}", normed_text(total)));
+    }
+
+    match (norms, age) {
+        (None, _) => {}
+        (Some(_), None) => {
+            ui.label("Enter the participant's age under Session to compare with the norms");
+        }
+        (Some(_), Some(age)) if measures.iter().any(|measure| measure.standing.is_none()) => {
+            ui.label(format!("Some scores have no norms for age {}", age));
+        }
+        (Some(norms), Some(_)) => {
+            ui.label(format!("Percentiles and z-scores from {}", norms.path.display()));
+        }
+    }
+}
+
+// The raw score, then its percentile and z-score where the norms give them
+fn normed_text(score: &NormedScore) -> String {
+    let Some(standing) = score.standing else {
+        return format!("{}", score.raw);
+    };
+    let mut parts = vec![];
+    if let Some(percentile) = standing.percentile {
+        parts.push(format!("{} %ile", percentile));
+    }
+    if let Some(z) = standing.z {
+        parts.push(format!("z {:+.2}", z));
+    }
+    format!("{} ({})", score.raw, parts.join(", "))
+}
+
+// Top entries of a leaderboard, best first
+fn show_ranking(ui: &mut egui::Ui, ranking: &[&RoundResult]) {
+    if ranking.is_empty() {
+        ui.label("No rounds in this category yet");
+    } else {
+        egui::Grid::new("leaderboard_grid").num_columns(6).striped(true).show(ui, |ui| {
+            ui.strong("#");
+            ui.strong("Player");
+            ui.strong("Game");
+            ui.strong("Score");
+            ui.strong("Time");
+            ui.strong("Date");
+            ui.end_row();
+
+            for (index, result) in ranking.iter().take(TOP_ENTRIES).enumerate() {
+                let category = Category::of(result);
+                ui.label(format!("{}", index + 1));
+                ui.label(result.profile.as_deref().unwrap_or("Guest"));
+                ui.label(format!(
+                    "{}, {}, {} items",
+                    category.game_type_label(),
+                    if category.strict { "Strict" } else { "Normal" },
+                    category.length
+                ));
+                ui.label(format!("{}", result.score));
+                match result.input_duration_ms {
+                    Some(millis) => ui.label(format!("{:.2} s", millis as f32 / 1000.0)),
+                    None => ui.label("-"),
+                };
+                ui.label(format_date(result.timestamp));
+                ui.end_row();
+            }
+        });
+    }
+}
+
+fn direction_color(direction: &InputDirection) -> egui::Color32 {
+    match direction {
+        InputDirection::Forward => egui::Color32::from_rgb(0, 150, 255),
+        InputDirection::Reverse => egui::Color32::from_rgb(255, 100, 100),
+        InputDirection::Sequencing => egui::Color32::from_rgb(120, 200, 80),
+    }
+}
+
+// Characters the game understands, by key
+fn key_char(key: egui::Key) -> Option<char> {
+    match key {
+        egui::Key::A => Some('A'),
+        egui::Key::B => Some('B'),
+        egui::Key::C => Some('C'),
+        egui::Key::D => Some('D'),
+        egui::Key::E => Some('E'),
+        egui::Key::F => Some('F'),
+        egui::Key::G => Some('G'),
+        egui::Key::H => Some('H'),
+        egui::Key::I => Some('I'),
+        egui::Key::J => Some('J'),
+        egui::Key::K => Some('K'),
+        egui::Key::L => Some('L'),
+        egui::Key::M => Some('M'),
+        egui::Key::N => Some('N'),
+        egui::Key::O => Some('O'),
+        egui::Key::P => Some('P'),
+        egui::Key::Q => Some('Q'),
+        egui::Key::R => Some('R'),
+        egui::Key::S => Some('S'),
+        egui::Key::T => Some('T'),
+        egui::Key::U => Some('U'),
+        egui::Key::V => Some('V'),
+        egui::Key::W => Some('W'),
+        egui::Key::X => Some('X'),
+        egui::Key::Y => Some('Y'),
+        egui::Key::Z => Some('Z'),
+        egui::Key::Num0 => Some('0'),
+        egui::Key::Num1 => Some('1'),
+        egui::Key::Num2 => Some('2'),
+        egui::Key::Num3 => Some('3'),
+        egui::Key::Num4 => Some('4'),
+        egui::Key::Num5 => Some('5'),
+        egui::Key::Num6 => Some('6'),
+        egui::Key::Num7 => Some('7'),
+        egui::Key::Num8 => Some('8'),
+        egui::Key::Num9 => Some('9'),
+        _ => None,
+    }
+}
@@ -0,0 +1,307 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::round_result::RoundResult;
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SequenceSharing {
+    Same,        // Everyone gets the round's sequence
+    Independent, // Each turn gets its own
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchFormat {
+    Rounds(u32),
+    Elimination, // Players who fail drop out, and the sequence grows each round
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotSeatStage {
+    WaitingForPlayer, // The device is being passed on, nothing of the last turn is shown
+    Playing,
+    RoundSummary,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct HotSeatPlayer {
+    pub name: String,
+    pub profile: Option<String>, // Where their rounds are saved, None for guests
+    pub eliminated_in: Option<u32>,
+    pub total_score: u32,
+    pub rounds_correct: u32,
+}
+
+impl HotSeatPlayer {
+    pub fn new(name: String, profile: Option<String>) -> Self {
+        Self {
+            name,
+            profile,
+            eliminated_in: None,
+            total_score: 0,
+            rounds_correct: 0,
+        }
+    }
+}
+
+// One player's turn in a round
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub player: usize,
+    pub success: bool,
+    pub input_duration_ms: Option<u64>,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct HotSeat {
+    pub players: Vec<HotSeatPlayer>,
+    pub sharing: SequenceSharing,
+    pub format: MatchFormat,
+    pub stage: HotSeatStage,
+    round: u32,        // 1-based
+    order: Vec<usize>, // Players taking a turn this round, in turn order
+    turn: usize,       // Index into order
+    round_seed: u64,
+    turns: Vec<Turn>,  // This round's, in turn order
+    rng: StdRng,
+}
+
+impl HotSeat {
+    pub fn new(players: Vec<HotSeatPlayer>, sharing: SequenceSharing, format: MatchFormat, seed: u64) -> Result<Self, String> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players.len()) {
+            return Err(format!("hot seat needs {} to {} players", MIN_PLAYERS, MAX_PLAYERS));
+        }
+        if format == MatchFormat::Rounds(0) {
+            return Err("play at least one round".to_string());
+        }
+
+        let mut hot_seat = Self {
+            players,
+            sharing,
+            format,
+            stage: HotSeatStage::WaitingForPlayer,
+            round: 0,
+            order: vec![],
+            turn: 0,
+            round_seed: 0,
+            turns: vec![],
+            rng: StdRng::seed_from_u64(seed),
+        };
+        hot_seat.start_next_round();
+        Ok(hot_seat)
+    }
+
+    fn start_next_round(&mut self) {
+        self.round += 1;
+        self.round_seed = self.rng.r#gen();
+        self.turns.clear();
+        self.turn = 0;
+
+        // A different player goes first each round
+        let active: Vec<usize> = (0..self.players.len()).filter(|i| self.players[*i].eliminated_in.is_none()).collect();
+        let shift = (self.round as usize - 1) % active.len();
+        self.order = active[shift..].iter().chain(&active[..shift]).copied().collect();
+        self.stage = HotSeatStage::WaitingForPlayer;
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn current_player(&self) -> &HotSeatPlayer {
+        &self.players[self.order[self.turn.min(self.order.len() - 1)]]
+    }
+
+    // Seed for the turn that is about to start
+    pub fn turn_seed(&mut self) -> u64 {
+        self.stage = HotSeatStage::Playing;
+        match self.sharing {
+            SequenceSharing::Same => self.round_seed,
+            SequenceSharing::Independent => self.rng.r#gen(),
+        }
+    }
+
+    // Sequence length this round, for a game set to `base`
+    pub fn sequence_length(&self, base: usize) -> usize {
+        match self.format {
+            MatchFormat::Rounds(_) => base,
+            MatchFormat::Elimination => base + self.round as usize - 1,
+        }
+    }
+
+    // Books the current player's finished round and moves on to the next turn
+    pub fn record(&mut self, result: &RoundResult) {
+        let player = self.order[self.turn];
+        self.players[player].total_score += result.score;
+        if result.success {
+            self.players[player].rounds_correct += 1;
+        }
+        self.turns.push(Turn {
+            player,
+            success: result.success,
+            input_duration_ms: result.input_duration_ms,
+            score: result.score,
+        });
+
+        self.turn += 1;
+        if self.turn < self.order.len() {
+            self.stage = HotSeatStage::WaitingForPlayer;
+            return;
+        }
+
+        // Everyone failing the same round doesn't knock everyone out
+        if self.format == MatchFormat::Elimination && self.turns.iter().any(|turn| turn.success) {
+            for turn in self.turns.iter().filter(|turn| !turn.success) {
+                self.players[turn.player].eliminated_in = Some(self.round);
+            }
+        }
+        self.stage = HotSeatStage::RoundSummary;
+    }
+
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    pub fn is_over(&self) -> bool {
+        match self.format {
+            MatchFormat::Rounds(rounds) => self.round >= rounds,
+            MatchFormat::Elimination => self.players.iter().filter(|player| player.eliminated_in.is_none()).count() <= 1,
+        }
+    }
+
+    // After the summary: the next round, or the final standings
+    pub fn continue_after_summary(&mut self) {
+        if self.is_over() {
+            self.stage = HotSeatStage::Finished;
+        } else {
+            self.start_next_round();
+        }
+    }
+
+    // Best first: still in the game, then the longest lasting, then by total score
+    pub fn standings(&self) -> Vec<&HotSeatPlayer> {
+        let mut standings: Vec<&HotSeatPlayer> = self.players.iter().collect();
+        standings.sort_by(|a, b| {
+            let lasted = |player: &HotSeatPlayer| player.eliminated_in.unwrap_or(u32::MAX);
+            lasted(b)
+                .cmp(&lasted(a))
+                .then(b.total_score.cmp(&a.total_score))
+        });
+        standings
+    }
+
+    // Everyone sharing first place
+    pub fn winners(&self) -> Vec<&HotSeatPlayer> {
+        let standings = self.standings();
+        let first = (standings[0].eliminated_in, standings[0].total_score);
+        standings
+            .into_iter()
+            .filter(|player| (player.eliminated_in, player.total_score) == first)
+            .collect()
+    }
+}
+
+// The players and rules being chosen before a hot seat game
+#[derive(Debug, Clone)]
+pub struct HotSeatSetup {
+    pub players: Vec<HotSeatPlayer>,
+    pub name_text: String,
+    pub sharing: SequenceSharing,
+    pub elimination: bool,
+    pub rounds: u32,
+}
+
+impl Default for HotSeatSetup {
+    fn default() -> Self {
+        Self {
+            players: vec![],
+            name_text: String::new(),
+            sharing: SequenceSharing::Same,
+            elimination: false,
+            rounds: 3,
+        }
+    }
+}
+
+impl HotSeatSetup {
+    pub fn add_player(&mut self, name: &str, profile: Option<String>) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("enter a name for the player".to_string());
+        }
+        if self.players.len() >= MAX_PLAYERS {
+            return Err(format!("at most {} players can take part", MAX_PLAYERS));
+        }
+        if self.players.iter().any(|player| player.name == name) {
+            return Err(format!("'{}' is already playing", name));
+        }
+        self.players.push(HotSeatPlayer::new(name.to_string(), profile));
+        Ok(())
+    }
+
+    pub fn format(&self) -> MatchFormat {
+        if self.elimination {
+            MatchFormat::Elimination
+        } else {
+            MatchFormat::Rounds(self.rounds)
+        }
+    }
+}
+
+impl MemoryGame {
+    pub fn start_hot_seat(&mut self, setup: &HotSeatSetup) -> Result<(), String> {
+        // N-Back rounds have no right or wrong, so nobody would ever be knocked out
+        if self.game_mode.is_continuous() {
+            return Err(format!("hot seat can't be played in {}", self.game_mode.short_name()));
+        }
+        let seed = self.next_seed();
+        self.hot_seat = Some(HotSeat::new(setup.players.clone(), setup.sharing, setup.format(), seed)?);
+        self.end_challenge();
+        self.phase = GamePhase::NotStarted;
+        Ok(())
+    }
+
+    // Called once the current player has the device
+    pub fn start_hot_seat_turn(&mut self) {
+        let Some(hot_seat) = &mut self.hot_seat else {
+            return;
+        };
+        let seed = hot_seat.turn_seed();
+
+        // Elimination rounds get longer without touching the player's settings
+        let base_length = self.sequence_length;
+        self.sequence_length = hot_seat.sequence_length(base_length);
+        // Alternating directions switch between rounds, so everyone in a round gets the same one
+        let rounds_started = self.rounds_started;
+        self.rounds_started = hot_seat.round() - 1;
+        self.start_round(seed);
+        self.sequence_length = base_length;
+        self.rounds_started = rounds_started;
+    }
+
+    // Books the finished turn; its results stay hidden until everyone has had theirs
+    pub fn record_hot_seat_turn(&mut self, result: &RoundResult) {
+        if let Some(hot_seat) = &mut self.hot_seat {
+            hot_seat.record(result);
+            self.phase = GamePhase::NotStarted;
+        }
+    }
+
+    pub fn quit_hot_seat(&mut self) {
+        self.hot_seat = None;
+        self.phase = GamePhase::NotStarted;
+    }
+
+    pub fn hot_seat(&self) -> Option<&HotSeat> {
+        self.hot_seat.as_ref()
+    }
+
+    pub fn hot_seat_mut(&mut self) -> Option<&mut HotSeat> {
+        self.hot_seat.as_mut()
+    }
+}
@@ -1,23 +1,48 @@
-use eframe::egui;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputDirection {
     Forward,
     Reverse,
+    Sequencing, // Smallest first: digits in ascending order, then letters alphabetically
 }
 
 impl InputDirection {
-    pub fn color(&self) -> egui::Color32 {
+    pub const ALL: [InputDirection; 3] = [InputDirection::Forward, InputDirection::Reverse, InputDirection::Sequencing];
+
+    // The items in the order they have to be entered
+    pub fn arrange<T: Clone, K: Ord>(&self, items: &[T], key: impl Fn(&T) -> K) -> Vec<T> {
+        let mut arranged = items.to_vec();
         match self {
-            InputDirection::Forward => egui::Color32::from_rgb(0, 150, 255),
-            InputDirection::Reverse => egui::Color32::from_rgb(255, 100, 100),
+            InputDirection::Forward => {}
+            InputDirection::Reverse => arranged.reverse(),
+            InputDirection::Sequencing => arranged.sort_by_key(key),
         }
+        arranged
     }
 
-    pub fn short_name(&self) -> &str {
+    pub fn short_name(&self) -> &'static str {
         match self {
             InputDirection::Forward => "FORWARD",
             InputDirection::Reverse => "REVERSE",
+            InputDirection::Sequencing => "SEQUENCING",
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            InputDirection::Forward => "forward",
+            InputDirection::Reverse => "reverse",
+            InputDirection::Sequencing => "sequencing",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "forward" => Ok(InputDirection::Forward),
+            "reverse" | "backward" => Ok(InputDirection::Reverse),
+            "sequencing" => Ok(InputDirection::Sequencing),
+            _ => Err(format!("unknown direction '{}'", text)),
         }
     }
 }
@@ -0,0 +1,297 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::game_mode::GameMode;
+use crate::input_direction::InputDirection;
+use crate::profile::ProfileStore;
+use crate::recall_test::TestVariant;
+use crate::round_result::RoundResult;
+
+pub const TOP_ENTRIES: usize = 10;
+
+// Rounds are only ranked against rounds played under the same conditions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Category {
+    pub game_mode: GameMode,
+    pub test_variant: Option<TestVariant>, // Only sequence rounds have test variants
+    pub strict: bool,
+    pub length: usize,
+    pub direction: Option<InputDirection>, // Continuous modes have no input direction
+}
+
+impl Category {
+    pub fn of(result: &RoundResult) -> Self {
+        Self {
+            game_mode: result.game_mode,
+            test_variant: (result.game_mode == GameMode::Sequence).then_some(result.test_variant),
+            strict: result.strict,
+            length: result.length(),
+            direction: (!result.game_mode.is_continuous()).then(|| result.direction.clone()),
+        }
+    }
+
+    pub fn game_type_label(&self) -> String {
+        match self.test_variant {
+            Some(variant) if variant != TestVariant::SerialRecall => {
+                format!("{} ({})", self.game_mode.short_name(), variant.short_name())
+            }
+            _ => self.game_mode.short_name().to_string(),
+        }
+    }
+}
+
+// Each part left as None matches any value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    pub game_type: Option<(GameMode, Option<TestVariant>)>,
+    pub strict: Option<bool>,
+    pub length: Option<usize>,
+    pub direction: Option<InputDirection>,
+}
+
+impl Filter {
+    pub fn exact(category: &Category) -> Self {
+        Self {
+            game_type: Some((category.game_mode, category.test_variant)),
+            strict: Some(category.strict),
+            length: Some(category.length),
+            direction: category.direction.clone(),
+        }
+    }
+
+    pub fn matches(&self, category: &Category) -> bool {
+        self.game_type.is_none_or(|game_type| game_type == (category.game_mode, category.test_variant))
+            && self.strict.is_none_or(|strict| strict == category.strict)
+            && self.length.is_none_or(|length| length == category.length)
+            && self.direction.as_ref().is_none_or(|direction| Some(direction) == category.direction.as_ref())
+    }
+
+    // As URL query parameters, e.g. "mode=sequence&variant=serial-recall&length=5"
+    pub fn to_query(&self) -> String {
+        let mut params = vec![];
+        if let Some((mode, variant)) = self.game_type {
+            params.push(format!("mode={}", mode.key()));
+            if let Some(variant) = variant {
+                params.push(format!("variant={}", variant.key()));
+            }
+        }
+        if let Some(strict) = self.strict {
+            params.push(format!("strict={}", strict));
+        }
+        if let Some(length) = self.length {
+            params.push(format!("length={}", length));
+        }
+        if let Some(direction) = &self.direction {
+            params.push(format!("direction={}", direction.key()));
+        }
+        params.join("&")
+    }
+
+    pub fn from_query(query: &str) -> Result<Self, String> {
+        let mut filter = Filter::default();
+        let mut variant = None;
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            match name {
+                "mode" => filter.game_type = Some((GameMode::parse(value)?, None)),
+                "variant" => variant = Some(TestVariant::parse(value)?),
+                "strict" => filter.strict = Some(value.parse().map_err(|_| format!("strict expects true or false, got '{}'", value))?),
+                "length" => filter.length = Some(value.parse().map_err(|_| format!("length expects a whole number, got '{}'", value))?),
+                "direction" => filter.direction = Some(InputDirection::parse(value)?),
+                _ => return Err(format!("unknown filter '{}'", name)),
+            }
+        }
+
+        if let Some((mode, _)) = filter.game_type {
+            // Sequence rounds are ranked per test variant, serial recall unless told otherwise
+            if mode == GameMode::Sequence {
+                filter.game_type = Some((mode, Some(variant.unwrap_or(TestVariant::SerialRecall))));
+            }
+        } else if variant.is_some() {
+            return Err("variant needs mode=sequence".to_string());
+        }
+        Ok(filter)
+    }
+}
+
+// What "best" means everywhere, leaderboards and personal bests alike: higher score first, then
+// the faster entry, then the earlier one. Only correct rounds are ranked at all.
+pub fn compare(a: &RoundResult, b: &RoundResult) -> Ordering {
+    b.score
+        .cmp(&a.score)
+        .then_with(|| {
+            a.input_duration_ms
+                .unwrap_or(u64::MAX)
+                .cmp(&b.input_duration_ms.unwrap_or(u64::MAX))
+        })
+        .then_with(|| a.timestamp.cmp(&b.timestamp))
+}
+
+// All rounds of all profiles on this machine
+#[derive(Debug, Clone, Default)]
+pub struct Leaderboard {
+    entries: Vec<RoundResult>,
+}
+
+impl Leaderboard {
+    pub fn load(store: &ProfileStore) -> Self {
+        let mut entries = vec![];
+        for name in store.list() {
+            for mut result in store.load_history(&name).unwrap_or_default() {
+                // Show the profile's current name, even for rounds played before a rename
+                result.profile = Some(name.clone());
+                entries.push(result);
+            }
+        }
+        Self { entries }
+    }
+
+    // Rounds kept as JSON lines, as the leaderboard server does; a missing file is an empty board
+    pub fn load_file(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self {
+                entries: text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect(),
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn rename_profile(&mut self, old: &str, new: &str) {
+        for result in &mut self.entries {
+            if result.profile.as_deref() == Some(old) {
+                result.profile = Some(new.to_string());
+            }
+        }
+    }
+
+    pub fn remove_profile(&mut self, name: &str) {
+        self.entries.retain(|result| result.profile.as_deref() != Some(name));
+    }
+
+    // Whether the same player already has a round with this seed; the timestamp is the
+    // player's to set, so it doesn't make a round new
+    pub fn contains(&self, result: &RoundResult) -> bool {
+        self.entries.iter().any(|other| other.profile == result.profile && other.seed == result.seed)
+    }
+
+    pub fn add(&mut self, result: RoundResult) {
+        self.entries.push(result);
+    }
+
+    // Failed rounds are kept with the rest but never ranked
+    fn ranked(&self) -> impl Iterator<Item = &RoundResult> {
+        self.entries.iter().filter(|result| result.success)
+    }
+
+    pub fn ranking(&self, filter: &Filter) -> Vec<&RoundResult> {
+        let mut ranking: Vec<&RoundResult> = self
+            .ranked()
+            .filter(|result| filter.matches(&Category::of(result)))
+            .collect();
+        ranking.sort_by(|a, b| compare(a, b));
+        ranking
+    }
+
+    // 1-based place of a round within its own category, None for a failed round
+    pub fn place(&self, result: &RoundResult) -> Option<usize> {
+        if !result.success {
+            return None;
+        }
+        let category = Category::of(result);
+        let ahead = self
+            .ranked()
+            .filter(|other| Category::of(other) == category && compare(other, result) == Ordering::Less)
+            .count();
+        Some(ahead + 1)
+    }
+
+    // Values present in the ranked entries, to offer as filter choices
+    pub fn categories(&self) -> Vec<Category> {
+        let mut categories: Vec<Category> = vec![];
+        for result in self.ranked() {
+            let category = Category::of(result);
+            if !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+        categories
+    }
+}
+
+// Whether a new correct round beats every correct round of the player's history in its category
+pub fn is_personal_best(history: &[RoundResult], result: &RoundResult) -> bool {
+    let category = Category::of(result);
+    result.success
+        && history
+            .iter()
+            .filter(|other| other.success && Category::of(other) == category)
+            .all(|other| compare(result, other) == Ordering::Less)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(seed: u64, success: bool, score: u32, input_duration_ms: Option<u64>) -> RoundResult {
+        RoundResult {
+            timestamp: 1_700_000_000 + seed,
+            profile: Some("Alice".to_string()),
+            game_mode: GameMode::Sequence,
+            test_variant: TestVariant::SerialRecall,
+            strict: false,
+            direction: InputDirection::Forward,
+            seed,
+            sequence: "12345".to_string(),
+            input: if success { "12345" } else { "12354" }.to_string(),
+            success,
+            input_duration_ms,
+            error: None,
+            score,
+            alphabet: None,
+            n: None,
+            target_rate: None,
+            accuracy_threshold: None,
+            keystrokes: vec![],
+            daily: None,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn higher_scores_rank_first_and_times_break_ties() {
+        let board = Leaderboard {
+            entries: vec![round(1, true, 50, Some(3000)), round(2, true, 60, Some(9000)), round(3, true, 50, Some(2000))],
+        };
+        let seeds: Vec<u64> = board.ranking(&Filter::default()).iter().map(|result| result.seed).collect();
+        assert_eq!(seeds, [2, 3, 1]);
+        assert_eq!(board.place(&round(4, true, 55, Some(1000))), Some(2));
+    }
+
+    #[test]
+    fn failed_rounds_are_not_ranked() {
+        let board = Leaderboard { entries: vec![round(1, false, 90, Some(1000)), round(2, true, 40, Some(5000))] };
+        let ranking = board.ranking(&Filter::default());
+        assert_eq!(ranking.len(), 1);
+        assert_eq!(ranking[0].seed, 2);
+        assert_eq!(board.place(&round(3, true, 45, Some(5000))), Some(1));
+        assert_eq!(board.place(&round(4, false, 99, Some(100))), None);
+        assert!(Leaderboard { entries: vec![round(1, false, 90, None)] }.categories().is_empty());
+    }
+
+    #[test]
+    fn personal_bests_follow_the_leaderboard_order() {
+        let history = vec![round(1, true, 50, Some(3000)), round(2, false, 80, Some(1000))];
+        assert!(is_personal_best(&history, &round(3, true, 60, Some(9000))));
+        assert!(is_personal_best(&history, &round(4, true, 50, Some(2000))));
+        assert!(!is_personal_best(&history, &round(5, true, 50, Some(4000))));
+        assert!(!is_personal_best(&[], &round(6, false, 100, Some(100))));
+
+        let history = [history, vec![round(3, true, 60, Some(9000))]].concat();
+        let board = Leaderboard { entries: history.clone() };
+        let bests = crate::profile::personal_bests(&history);
+        assert_eq!(bests["Sequence"].seed, board.ranking(&Filter::default())[0].seed);
+    }
+}
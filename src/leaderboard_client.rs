@@ -0,0 +1,62 @@
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::http;
+use crate::leaderboard::Filter;
+use crate::round_result::RoundResult;
+
+// Talks to a leaderboard_server at "host:port"
+
+// Answers from requests made in the background, picked up by the GUI each frame
+#[derive(Debug, Clone)]
+pub enum ServerReply {
+    Submitted(Result<usize, String>),
+    Rankings(Filter, Rankings),
+}
+
+pub type Rankings = Result<Vec<RoundResult>, String>;
+
+pub type Replies = Arc<Mutex<Vec<ServerReply>>>;
+
+// The network can be slow, so the GUI doesn't wait for it
+pub fn submit_in_background(address: String, result: RoundResult, replies: Replies) {
+    thread::spawn(move || {
+        let reply = ServerReply::Submitted(submit(&address, &result));
+        replies.lock().unwrap().push(reply);
+    });
+}
+
+pub fn fetch_in_background(address: String, filter: Filter, replies: Replies) {
+    thread::spawn(move || {
+        let rankings = fetch_rankings(&address, &filter);
+        replies.lock().unwrap().push(ServerReply::Rankings(filter, rankings));
+    });
+}
+
+// Sends a finished round and returns its place in its category
+pub fn submit(address: &str, result: &RoundResult) -> Result<usize, String> {
+    // Participant details stay on this machine
+    let result = RoundResult { session: None, ..result.clone() };
+    let body = serde_json::to_string(&result).map_err(|e| e.to_string())?;
+    let reply = reply_json(http::send(address, "POST", "/rounds", &body)?)?;
+    reply["place"]
+        .as_u64()
+        .map(|place| place as usize)
+        .ok_or_else(|| "the server sent no place".to_string())
+}
+
+pub fn fetch_rankings(address: &str, filter: &Filter) -> Result<Vec<RoundResult>, String> {
+    let reply = reply_json(http::send(address, "GET", &format!("/rankings?{}", filter.to_query()), "")?)?;
+    serde_json::from_value(reply).map_err(|e| format!("the server sent unexpected rankings: {}", e))
+}
+
+// The JSON of a successful reply, or the error the server gave
+fn reply_json((status, body): (u16, String)) -> Result<Value, String> {
+    let value: Value = serde_json::from_str(&body).map_err(|_| format!("the server answered {} without JSON", status))?;
+    if (200..300).contains(&status) {
+        Ok(value)
+    } else {
+        Err(value["error"].as_str().map_or_else(|| format!("the server answered {}", status), str::to_string))
+    }
+}
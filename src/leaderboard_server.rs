@@ -0,0 +1,106 @@
+use serde_json::json;
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use crate::http::{self, Request};
+use crate::leaderboard::{Filter, Leaderboard, TOP_ENTRIES};
+use crate::profile::validate_name;
+use crate::replay::{input_duration_ms, verify};
+use crate::round_result::RoundResult;
+
+// Shared leaderboard for a local network, kept as JSON lines in one file
+//
+//   POST /rounds      a RoundResult; replayed before it is accepted, answers {"place": N}
+//   GET  /rankings    top entries, filtered by mode, variant, strict, length and direction
+pub struct LeaderboardServer {
+    path: PathBuf,
+    leaderboard: Leaderboard,
+}
+
+impl LeaderboardServer {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let leaderboard = Leaderboard::load_file(&path)?;
+        Ok(Self { path, leaderboard })
+    }
+
+    // Answers requests one at a time, until the process is stopped
+    pub fn serve(&mut self, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("leaderboard_server: {}", e);
+                    continue;
+                }
+            };
+
+            let (status, body) = match http::read_request(&mut stream) {
+                Ok(request) => self.handle(&request),
+                Err(e) => (400, error_body(&e)),
+            };
+            if let Err(e) = http::write_response(&mut stream, status, &body) {
+                eprintln!("leaderboard_server: {}", e);
+            }
+        }
+    }
+
+    // Status code and JSON body for a request
+    pub fn handle(&mut self, request: &Request) -> (u16, String) {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/rounds") => self.submit(&request.body),
+            ("GET", "/rankings") => self.rankings(&request.query),
+            (_, "/rounds" | "/rankings") => (405, error_body("method not allowed")),
+            _ => (404, error_body("not found")),
+        }
+    }
+
+    fn submit(&mut self, body: &str) -> (u16, String) {
+        let mut result: RoundResult = match serde_json::from_str(body) {
+            Ok(result) => result,
+            Err(e) => return (400, error_body(&format!("not a round: {}", e))),
+        };
+
+        let name = result.profile.clone().unwrap_or_default();
+        if let Err(e) = validate_name(&name) {
+            return (422, error_body(&e));
+        }
+        if !result.success {
+            return (422, error_body("only correct rounds are ranked"));
+        }
+        if let Err(e) = verify(&result) {
+            return (422, error_body(&format!("replay failed, {}", e)));
+        }
+
+        if self.leaderboard.contains(&result) {
+            return (422, error_body("this round was already submitted"));
+        }
+
+        // Times break ties, so they come from the log rather than the player's word
+        result.input_duration_ms = input_duration_ms(&result.keystrokes);
+
+        // The replay is checked, rankings don't need the keystrokes; study details aren't shared
+        result.keystrokes.clear();
+        result.session = None;
+        if let Err(e) = result.append_to(&self.path) {
+            return (500, error_body(&format!("could not save the round: {}", e)));
+        }
+
+        let place = self.leaderboard.place(&result);
+        self.leaderboard.add(result);
+        (201, json!({ "place": place }).to_string())
+    }
+
+    fn rankings(&self, query: &str) -> (u16, String) {
+        let filter = match Filter::from_query(query) {
+            Ok(filter) => filter,
+            Err(e) => return (400, error_body(&e)),
+        };
+        let ranking: Vec<&RoundResult> = self.leaderboard.ranking(&filter).into_iter().take(TOP_ENTRIES).collect();
+        (200, json!(ranking).to_string())
+    }
+}
+
+fn error_body(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
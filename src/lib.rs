@@ -0,0 +1,37 @@
+pub mod alphabet;
+pub mod analytics;
+pub mod block_grid;
+pub mod challenge;
+pub mod char_type;
+pub mod cli;
+pub mod complex_span;
+pub mod digit_span;
+pub mod direction_policy;
+pub mod dual_n_back;
+pub mod event_feed;
+pub mod experimenter;
+pub mod game_event;
+pub mod game_mode;
+pub mod hot_seat;
+pub mod http;
+pub mod input_direction;
+pub mod leaderboard;
+pub mod leaderboard_client;
+pub mod leaderboard_server;
+pub mod markers;
+pub mod memory_game;
+pub mod n_back;
+pub mod norms;
+pub mod profile;
+pub mod quest;
+pub mod race;
+pub mod recall_test;
+pub mod replay;
+pub mod round_result;
+pub mod session;
+pub mod settings;
+pub mod spectator;
+pub mod study;
+pub mod websocket;
+
+pub use memory_game::{GamePhase, MemoryGame};
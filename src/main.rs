@@ -1,483 +1,85 @@
 use eframe::egui;
-use rand::Rng;
-use std::time::{Duration, Instant};
 
-mod char_type;
-mod input_direction;
+use memory_game::cli::{CliOptions, USAGE};
+use memory_game::leaderboard::Leaderboard;
+use memory_game::profile::ProfileStore;
+use memory_game::MemoryGame;
 
-use char_type::CharType;
-use input_direction::InputDirection;
+mod gui;
 
-
-#[derive(Debug, Clone)]
-enum GamePhase {
-    NotStarted,
-    ShowingSequence,
-    Inputting,
-    GameOver,
-    Success,
-}
-
-struct MemoryGame {
-    sequence: Vec<CharType>,
-    user_input: Vec<CharType>,
-    current_index: usize,
-    phase: GamePhase,
-    // show_duration: Duration,
-    error_message: Option<String>,
-    sequence_display_timer: f32,
-    current_sequence_index: usize,
-    show_sequence: bool,
-    normal_mode: bool,
-    input_direction: InputDirection,
-    input_start_time: Option<Instant>, // Track when user starts inputting
-    input_duration: Option<Duration>,  // Track total input time
-}
-
-
-impl MemoryGame {
-    fn new() -> Self {
-        Self {
-            sequence: vec![],
-            user_input: vec![],
-            current_index: 0,
-            phase: GamePhase::NotStarted,
-            // show_duration: Duration::from_millis(800), // Just a constant, not used
-            error_message: None,
-            sequence_display_timer: 0.0,
-            current_sequence_index: 0,
-            show_sequence: false,
-            normal_mode: true,
-            input_direction: InputDirection::Forward,
-            input_start_time: None,
-            input_duration: None,
-        }
-    }
-
-    fn generate_sequence(&mut self, length: usize) {
-        let mut rng = rand::thread_rng();
-        self.sequence = Vec::with_capacity(length);
-
-        for _ in 0..length {
-            if rng.gen_bool(0.5) {
-                // Generate a number (0-9)
-                let num = rng.gen_range(b'0'..=b'9');
-                self.sequence.push(CharType::Number(num));
-            } else {
-                // Generate a letter (A-Z)
-                let letter = rng.gen_range(b'A'..=b'Z');
-                self.sequence.push(CharType::Letter(letter));
-            }
+fn main() -> Result<(), eframe::Error> {
+    let options = match CliOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("memory_game: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
         }
+    };
 
-        // Randomly decide input direction
-        self.input_direction = if rng.gen_bool(0.5) {
-            InputDirection::Forward
-        } else {
-            InputDirection::Reverse
-        };
-    }
-
-    fn start_game(&mut self) {
-        self.generate_sequence(5);
-        self.user_input.clear();
-        self.current_index = self.sequence.len();
-        self.phase = GamePhase::ShowingSequence;
-        self.error_message = None;
-        self.sequence_display_timer = 0.0;
-        self.current_sequence_index = 0;
-        self.show_sequence = true;
-        self.input_start_time = None;
-        self.input_duration = None;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
     }
 
-    fn handle_key_input(&mut self, ctx: &egui::Context) {
-        ctx.input(|i| {
-            for event in &i.raw.events {
-                if let egui::Event::Key { key, pressed, modifiers: _, physical_key: _, repeat: _ } = event {
-                    if *pressed {
-                        match key {
-                            egui::Key::A => self.process_input(CharType::Letter(b'A')),
-                            egui::Key::B => self.process_input(CharType::Letter(b'B')),
-                            egui::Key::C => self.process_input(CharType::Letter(b'C')),
-                            egui::Key::D => self.process_input(CharType::Letter(b'D')),
-                            egui::Key::E => self.process_input(CharType::Letter(b'E')),
-                            egui::Key::F => self.process_input(CharType::Letter(b'F')),
-                            egui::Key::G => self.process_input(CharType::Letter(b'G')),
-                            egui::Key::H => self.process_input(CharType::Letter(b'H')),
-                            egui::Key::I => self.process_input(CharType::Letter(b'I')),
-                            egui::Key::J => self.process_input(CharType::Letter(b'J')),
-                            egui::Key::K => self.process_input(CharType::Letter(b'K')),
-                            egui::Key::L => self.process_input(CharType::Letter(b'L')),
-                            egui::Key::M => self.process_input(CharType::Letter(b'M')),
-                            egui::Key::N => self.process_input(CharType::Letter(b'N')),
-                            egui::Key::O => self.process_input(CharType::Letter(b'O')),
-                            egui::Key::P => self.process_input(CharType::Letter(b'P')),
-                            egui::Key::Q => self.process_input(CharType::Letter(b'Q')),
-                            egui::Key::R => self.process_input(CharType::Letter(b'R')),
-                            egui::Key::S => self.process_input(CharType::Letter(b'S')),
-                            egui::Key::T => self.process_input(CharType::Letter(b'T')),
-                            egui::Key::U => self.process_input(CharType::Letter(b'U')),
-                            egui::Key::V => self.process_input(CharType::Letter(b'V')),
-                            egui::Key::W => self.process_input(CharType::Letter(b'W')),
-                            egui::Key::X => self.process_input(CharType::Letter(b'X')),
-                            egui::Key::Y => self.process_input(CharType::Letter(b'Y')),
-                            egui::Key::Z => self.process_input(CharType::Letter(b'Z')),
-                            egui::Key::Num0 => self.process_input(CharType::Number(b'0')),
-                            egui::Key::Num1 => self.process_input(CharType::Number(b'1')),
-                            egui::Key::Num2 => self.process_input(CharType::Number(b'2')),
-                            egui::Key::Num3 => self.process_input(CharType::Number(b'3')),
-                            egui::Key::Num4 => self.process_input(CharType::Number(b'4')),
-                            egui::Key::Num5 => self.process_input(CharType::Number(b'5')),
-                            egui::Key::Num6 => self.process_input(CharType::Number(b'6')),
-                            egui::Key::Num7 => self.process_input(CharType::Number(b'7')),
-                            egui::Key::Num8 => self.process_input(CharType::Number(b'8')),
-                            egui::Key::Num9 => self.process_input(CharType::Number(b'9')),
-                            egui::Key::Backspace | egui::Key::Delete => {
-                                self.delete_last();
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        });
-    }
-
-    fn process_input(&mut self, input: CharType) {
-        // Only process input when in Inputting phase
-        if !matches!(self.phase, GamePhase::Inputting) {
-            return;
-        }
+    let store = ProfileStore::default_location();
 
-        self.user_input.push(input.clone());
-
-        // Check if the input matches the expected character based on direction
-        let expected_index = match self.input_direction {
-            InputDirection::Forward => self.user_input.len() - 1, // 0-indexed for forward
-            InputDirection::Reverse => self.sequence.len() - self.user_input.len(), // Reverse indexing
+    if let Some(command) = &options.profile_command {
+        let Some(store) = &store else {
+            eprintln!("memory_game: no config directory to keep profiles in");
+            std::process::exit(1);
         };
-
-        if expected_index < self.sequence.len() &&
-           self.sequence[expected_index] != input {
-            if self.normal_mode {
-                // In Normal mode, just record the error but continue
-                // Don't show the error message immediately - only show when sequence is complete
-            } else {
-                // In Strict mode, end the game immediately
-                self.error_message = Some(format!(
-                    "Wrong! Expected '{}' but got '{}'",
-                    self.sequence[expected_index].as_char(),
-                    input.as_char()
-                ));
-                self.phase = GamePhase::GameOver;
-                return;
-            }
-        }
-
-        // Check if the sequence is complete
-        if self.user_input.len() == self.sequence.len() {
-            // Record the total input time
-            if let Some(start_time) = self.input_start_time {
-                self.input_duration = Some(start_time.elapsed());
+        match CliOptions::run_profile_command(command, store) {
+            Ok(output) => {
+                println!("{}", output);
+                return Ok(());
             }
-
-            // Check if all characters were correct
-            let mut all_correct = true;
-
-            for (i, user_char) in self.user_input.iter().enumerate() {
-                let seq_index = match self.input_direction {
-                    InputDirection::Forward => i,
-                    InputDirection::Reverse => self.sequence.len() - i - 1, // Reverse order
-                };
-
-                if self.sequence[seq_index] != *user_char {
-                    all_correct = false;
-                    break;
-                }
-            }
-
-            if all_correct {
-                self.phase = GamePhase::Success;
-            } else {
-                // Show the error message only when the sequence is complete
-                if !self.normal_mode {
-                    // In strict mode, we already have an error message
-                } else {
-                    // In normal mode, show an error message
-                    self.error_message = Some("Sequence is incorrect!".to_string());
-                }
-                self.phase = GamePhase::GameOver;
+            Err(e) => {
+                eprintln!("memory_game: {}", e);
+                std::process::exit(1);
             }
         }
     }
 
-    fn delete_last(&mut self) {
-        // Only allow deletion when in Inputting phase
-        if !matches!(self.phase, GamePhase::Inputting) {
-            return;
-        }
-
-        if !self.user_input.is_empty() {
-            self.user_input.pop();
-            // Don't clear error message when deleting in Normal mode
-            // Only clear error if we're in Strict mode and there are no more errors
-            if !self.normal_mode {
-                // In Strict mode, if we've deleted the incorrect character, clear error
-                if self.user_input.len() > 0 {
-                    let expected_index = match self.input_direction {
-                        InputDirection::Forward => self.user_input.len() - 1,
-                        InputDirection::Reverse => self.sequence.len() - self.user_input.len(),
-                    };
-
-                    if expected_index < self.sequence.len() {
-                        let last_char = self.user_input.last().unwrap();
-                        if self.sequence[expected_index] == *last_char {
-                            self.error_message = None;
-                        }
-                    }
-                } else {
-                    self.error_message = None;
-                }
-            }
-        }
+    let mut game = MemoryGame::new();
+    game.profiles = store.clone();
+    if let Some(store) = &store {
+        game.leaderboard = Leaderboard::load(store);
     }
-}
-
-impl eframe::App for MemoryGame {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle keyboard input
-        self.handle_key_input(ctx);
-
-        // Update the sequence display timer (only during showing sequence phase)
-        if matches!(self.phase, GamePhase::ShowingSequence) {
-            self.sequence_display_timer += ctx.input(|i| i.stable_dt).min(0.1);
-
-            // Show each character for 0.8 seconds
-            let chars_per_second = 1.0 / 0.8;
-            self.current_sequence_index = (self.sequence_display_timer * chars_per_second) as usize;
 
-            if self.current_sequence_index >= self.sequence.len() {
-                // Finished showing sequence, move to input phase
-                self.phase = GamePhase::Inputting;
-                // Start the timer when transitioning to Inputting phase
-                self.input_start_time = Some(Instant::now());
-            }
+    // --profile picks (or creates) the profile, otherwise the last one used is resumed
+    let mut active = store.as_ref().and_then(|store| store.active());
+    if let (Some(store), Some(name)) = (&store, &options.profile) {
+        if !store.exists(name)
+            && let Err(e) = store.create(name) {
+            eprintln!("memory_game: {}", e);
+            std::process::exit(1);
         }
+        active = Some(name.clone());
+    }
+    game.show_profiles = store.is_some() && active.is_none();
+    game.switch_profile(active);
+    if store.is_none() {
+        // Without a config dir the name is still recorded with results
+        game.profile = options.profile.clone();
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(20.0);
-
-                ui.heading("Memory Game");
-
-                // Mode selection - Normal mode first
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.normal_mode, true, "Normal");
-                    ui.radio_value(&mut self.normal_mode, false, "Strict");
-                });
-
-                ui.separator();
-
-                match &self.phase {
-                    GamePhase::NotStarted => {
-                        ui.heading("Click 'Start New Game' to begin!");
-
-                        ui.label("Instructions:");
-                        ui.label("1. Remember the sequence of numbers and letters shown on screen");
-                        ui.label("2. Enter them in the required order when prompted");
-                        ui.label("3. Use Backspace/Delete to remove the last entry if you make a mistake");
-                    }
-
-                    GamePhase::ShowingSequence => {
-                        ui.heading("Remember the sequence:");
-
-                        // Show only up to current_sequence_index
-                        let mut seq_text = String::new();
-                        for i in 0..=self.current_sequence_index.min(self.sequence.len()) {
-                            if i < self.sequence.len() {
-                                seq_text.push_str(&format!("{} ", self.sequence[i].as_char()));
-                            }
-                        }
-
-                        ui.heading(&seq_text);
-
-                        // Progress indicator
-                        ui.label(format!(
-                            "Showing sequence... {}/{}",
-                            (self.current_sequence_index + 1).min(self.sequence.len()),
-                            self.sequence.len()
-                        ));
-                    }
-
-                    GamePhase::Inputting => {
-                        ui.heading("Enter the sequence:");
-
-                        // Show visual indicator for input direction with enhanced styling
-                        ui.horizontal(|ui| {
-                            ui.label("Direction: ");
-                            ui.colored_label(
-                                self.input_direction.color(),
-                                format!("{}", self.input_direction.short_name())
-                            );
-                        });
-
-                        // Show what the user has entered so far
-                        let entered: String = self.user_input
-                            .iter()
-                            .map(|c| c.as_char().to_string())
-                            .collect::<Vec<String>>()
-                            .join(" ");
-
-                        ui.heading(format!("Your input: [{}]", entered));
-
-                        // Only show error in Strict mode during input
-                        if !self.normal_mode {
-                            if let Some(error) = &self.error_message {
-                                ui.colored_label(egui::Color32::RED, error);
-                            }
-                        }
-
-                        ui.label("Press the corresponding keys on your keyboard");
-                        ui.label("Press Backspace/Delete to remove the last entry");
-
-                        ui.separator();
-
-                        // Show the remaining count
-                        ui.label(format!(
-                            "Characters remaining: {}",
-                            self.sequence.len() - self.user_input.len()
-                        ));
-                    }
-
-                    GamePhase::GameOver => {
-                        ui.heading("Game Over!");
-
-                        if let Some(error) = &self.error_message {
-                            ui.colored_label(egui::Color32::RED, error);
-                        }
-
-                        // Show both sequences for comparison
-                        ui.label("Your input:");
-                        let user_seq: String = self.user_input
-                            .iter()
-                            .map(|c| c.as_char().to_string())
-                            .collect::<Vec<String>>()
-                            .join(", ");
-                        ui.heading(&user_seq);
-
-                        // Show the correct sequence based on input direction
-                        let correct_seq: String = match self.input_direction {
-                            InputDirection::Forward => {
-                                self.sequence
-                                    .iter()
-                                    .map(|c| c.as_char().to_string())
-                                    .collect::<Vec<String>>()
-                                    .join(", ")
-                            }
-                            InputDirection::Reverse => {
-                                self.sequence
-                                    .iter()
-                                    .rev()
-                                    .map(|c| c.as_char().to_string())
-                                    .collect::<Vec<String>>()
-                                    .join(", ")
-                            }
-                        };
-
-                        ui.horizontal(|ui| {
-                            ui.label("Correct sequence: ");
-                            ui.colored_label(
-                                self.input_direction.color(),
-                                format!("{}", self.input_direction.short_name())
-                            );
-                        });
-                        ui.heading(&correct_seq);
-
-                        // Show input time if available
-                        if let Some(duration) = self.input_duration {
-                            let seconds = duration.as_secs_f32();
-                            ui.label(format!("Time taken: {:.2} seconds", seconds));
-                        }
-
-                        if self.normal_mode {
-                            ui.label("In Normal mode, the sequence was checked after you entered all characters.");
-                        }
-                    }
-
-                    GamePhase::Success => {
-                        ui.horizontal(|ui| {
-                            ui.heading("Congratulations!");
-                        });
-
-                        ui.colored_label(egui::Color32::GREEN,
-                            format!("You remembered the sequence correctly in {}!",
-                                    self.input_direction.short_name())
-                        );
-
-                        // Show the sequence for confirmation
-                        ui.label("The sequence was:");
-                        let correct_seq: String = self.sequence
-                            .iter()
-                            .map(|c| c.as_char().to_string())
-                            .collect::<Vec<String>>()
-                            .join(", ");
-                        ui.heading(&correct_seq);
-
-                        // Show input time if available
-                        if let Some(duration) = self.input_duration {
-                            let seconds = duration.as_secs_f32();
-                            ui.label(format!("Time taken: {:.2} seconds", seconds));
-                        }
-                    }
-                }
-
-                ui.add_space(30.0);
-
-                ui.horizontal(|ui| {
-                    if ui.button("Start New Game").clicked() {
-                        self.start_game();
-                    }
-
-                    // if matches!(self.phase, GamePhase::Inputting) {
-                    //     if ui.button("Delete Last").clicked() {
-                    //         self.delete_last();
-                    //     }
-                    // }
-                });
-
-                ui.add_space(20.0);
-
-                ui.collapsing("Game Instructions", |ui| {
-                    ui.label("1. Remember the sequence of numbers and letters shown on screen");
-                    ui.label("2. Enter them in the required order when prompted");
-                    ui.label("3. Use Backspace or Delete key to remove the last entry if you make a mistake");
-                    ui.label("4. Be quick - you need to remember and enter the sequence correctly!");
-                    ui.separator();
-                    ui.label("Modes:");
-                    ui.label("- Normal: Enter the entire sequence, errors are checked at the end");
-                    ui.label("- Strict: Game ends immediately if you make a mistake");
-                });
-            });
-        });
+    // Command-line options win over the settings file
+    options.apply(&mut game);
 
-        // Request repaint for animation
-        ctx.request_repaint();
-    }
-}
+    let viewport = egui::ViewportBuilder::default()
+        .with_inner_size(options.window_size.unwrap_or([600.0, 500.0]))
+        .with_min_inner_size([300.0, 200.0])
+        .with_title("Memory Game")
+        .with_fullscreen(options.fullscreen);
 
-fn main() -> Result<(), eframe::Error> {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([600.0, 500.0])
-            .with_min_inner_size([300.0, 200.0])
-            .with_title("Memory Game"),
+    let native_options = eframe::NativeOptions {
+        viewport,
         ..Default::default()
     };
 
     eframe::run_native(
         "Memory Game",
-        options,
-        Box::new(|_cc| Ok(Box::new(MemoryGame::new()))),
+        native_options,
+        Box::new(|_cc| Ok(Box::new(gui::MemoryGameApp::new(game)))),
     )
 }
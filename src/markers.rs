@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::game_event::GameEvent;
+use crate::memory_game::{GamePhase, MemoryGame};
+
+// Event markers for recordings running next to the game (EEG, physiology), one UDP datagram
+// each, e.g. {"number":12,"code":10,"marker":"item_onset","item":3,"t":41.207515}.
+// Numbers count up from 1 so lost datagrams show; t is seconds since the markers were opened.
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerKind {
+    RoundStart,
+    ItemOnset,
+    ItemOffset,
+    InputStart,
+    Keystroke,
+    RoundEnd,
+}
+
+// Code sent with each kind of marker, to match what the recording software expects
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkerCodes {
+    pub round_start: u16,
+    pub item_onset: u16,
+    pub item_offset: u16,
+    pub input_start: u16,
+    pub keystroke: u16,
+    pub round_end: u16,
+}
+
+impl Default for MarkerCodes {
+    fn default() -> Self {
+        Self {
+            round_start: 1,
+            item_onset: 10,
+            item_offset: 11,
+            input_start: 20,
+            keystroke: 30,
+            round_end: 40,
+        }
+    }
+}
+
+impl MarkerCodes {
+    // A TOML file such as `item_onset = 128`; codes left out keep their defaults
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn code(&self, kind: MarkerKind) -> u16 {
+        match kind {
+            MarkerKind::RoundStart => self.round_start,
+            MarkerKind::ItemOnset => self.item_onset,
+            MarkerKind::ItemOffset => self.item_offset,
+            MarkerKind::InputStart => self.input_start,
+            MarkerKind::Keystroke => self.keystroke,
+            MarkerKind::RoundEnd => self.round_end,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Marker {
+    number: u64,
+    code: u16,
+    marker: MarkerKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    success: Option<bool>,
+    t: f64,
+}
+
+#[derive(Debug)]
+pub struct MarkerStream {
+    pub target: String,
+    pub codes: MarkerCodes,
+    socket: UdpSocket,
+    opened: Instant,
+    sent: u64,
+    queued: Vec<(Instant, GameEvent)>, // Engine events waiting for the end of the frame
+    round_start: Option<Instant>,      // Of the round the last queued events belong to
+    visible: Vec<usize>,               // Items on screen in the last frame
+    answering: bool,
+}
+
+impl MarkerStream {
+    pub fn open(target: &str, codes: MarkerCodes) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+        socket.connect(target).map_err(|e| format!("could not send markers to {}: {}", target, e))?;
+        Ok(Self {
+            target: target.to_string(),
+            codes,
+            socket,
+            opened: Instant::now(),
+            sent: 0,
+            queued: vec![],
+            round_start: None,
+            visible: vec![],
+            answering: false,
+        })
+    }
+
+    // Keystrokes are stamped with when they were logged, counted from `round_start`, the start of
+    // the round being played; a round that started this frame is the last one in `events`
+    pub fn queue(&mut self, events: &[GameEvent], round_start: Option<Instant>) {
+        let now = Instant::now();
+        let new_round = events.iter().any(|event| matches!(event, GameEvent::RoundStarted { .. }));
+        let mut start = if new_round { self.round_start } else { round_start };
+
+        for event in events {
+            let time = match event {
+                GameEvent::RoundStarted { .. } => {
+                    start = round_start;
+                    round_start.unwrap_or(now)
+                }
+                GameEvent::Input(keystroke) => start.map_or(now, |start| start + Duration::from_millis(keystroke.ms)),
+                _ => now,
+            };
+            self.queued.push((time, event.clone()));
+        }
+        self.round_start = round_start;
+    }
+
+    // Called once per frame after drawing, with the items that frame shows. Onsets and offsets
+    // are stamped when the frame that draws or removes the item has been built, not when the
+    // engine moved on; the display shows it up to a refresh or so later.
+    pub fn frame(&mut self, visible: &[usize], answering: bool) {
+        let now = Instant::now();
+        let mut round_end = None;
+
+        for (time, event) in std::mem::take(&mut self.queued) {
+            match event {
+                GameEvent::RoundStarted { .. } => self.send(MarkerKind::RoundStart, None, None, time),
+                GameEvent::Input(_) => self.send(MarkerKind::Keystroke, None, None, time),
+                GameEvent::RoundFinished(result) => round_end = Some(result.success),
+                GameEvent::ItemShown(_) | GameEvent::PhaseChanged(_) => {}
+            }
+        }
+
+        let previous = std::mem::replace(&mut self.visible, visible.to_vec());
+        for item in &previous {
+            if !visible.contains(item) {
+                self.send(MarkerKind::ItemOffset, Some(*item), None, now);
+            }
+        }
+        for item in visible {
+            if !previous.contains(item) {
+                self.send(MarkerKind::ItemOnset, Some(*item), None, now);
+            }
+        }
+
+        if answering && !self.answering {
+            self.send(MarkerKind::InputStart, None, None, now);
+        }
+        self.answering = answering;
+
+        // The round is over on screen once its last frame has been replaced
+        if let Some(success) = round_end {
+            self.send(MarkerKind::RoundEnd, None, Some(success), now);
+        }
+    }
+
+    fn send(&mut self, kind: MarkerKind, item: Option<usize>, success: Option<bool>, time: Instant) {
+        self.sent += 1;
+        let marker = Marker {
+            number: self.sent,
+            code: self.codes.code(kind),
+            marker: kind,
+            item,
+            success,
+            t: time.duration_since(self.opened).as_secs_f64(),
+        };
+        // A recorder that isn't listening yet mustn't stop the game
+        let _ = self.socket.send(serde_json::to_string(&marker).unwrap_or_default().as_bytes());
+    }
+}
+
+impl MemoryGame {
+    pub fn start_markers(&mut self, target: &str, codes: MarkerCodes) -> Result<(), String> {
+        self.markers = Some(MarkerStream::open(target, codes)?);
+        self.publish_events = true;
+        Ok(())
+    }
+
+    // Called once per frame by the front end with the items it drew
+    pub fn mark_frame(&mut self, visible_items: &[usize]) {
+        let answering = self.in_round() && !matches!(self.phase, GamePhase::ShowingSequence);
+        if let Some(markers) = &mut self.markers {
+            markers.frame(visible_items, answering);
+        }
+    }
+}
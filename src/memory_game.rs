@@ -0,0 +1,753 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::alphabet::Alphabet;
+use crate::block_grid::{BlockGrid, BLOCK_COUNT};
+use crate::char_type::CharType;
+use crate::challenge::{daily_date, Challenge};
+use crate::complex_span::ComplexSpan;
+use crate::digit_span::DigitSpan;
+use crate::direction_policy::DirectionPolicy;
+use crate::dual_n_back::{DualNBack, MatchStream};
+use crate::event_feed::EventFeed;
+use crate::experimenter::ExperimenterConsole;
+use crate::game_event::GameEvent;
+use crate::game_mode::GameMode;
+use crate::hot_seat::HotSeat;
+use crate::input_direction::InputDirection;
+use crate::leaderboard::Leaderboard;
+use crate::markers::MarkerStream;
+use crate::n_back::NBack;
+use crate::norms::Norms;
+use crate::profile::ProfileStore;
+use crate::quest::Quest;
+use crate::race::Race;
+use crate::recall_test::{RecognitionProbe, TestScores, TestVariant};
+use crate::replay::{Action, Keystroke};
+use crate::round_result::{unix_timestamp, RoundResult};
+use crate::session::{SessionInfo, SessionSetup};
+use crate::settings::Settings;
+use crate::spectator::{Broadcast, Viewer};
+use crate::study::{Study, StudyDesign};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GamePhase {
+    NotStarted,
+    ShowingSequence,
+    Inputting,
+    ProbeRecall,  // Single keystroke answer for one probed position
+    Recognition,  // Same/different judgement on a shown sequence
+    GameOver,
+    Success,
+}
+
+pub struct MemoryGame {
+    pub(crate) sequence: Vec<CharType>,
+    pub(crate) user_input: Vec<CharType>,
+    pub(crate) current_index: usize,
+    pub(crate) phase: GamePhase,
+    // show_duration: Duration,
+    pub(crate) error_message: Option<String>,
+    pub(crate) sequence_display_timer: f32,
+    pub(crate) current_sequence_index: usize,
+    pub(crate) show_sequence: bool,
+    pub normal_mode: bool,
+    pub(crate) input_direction: InputDirection,
+    pub(crate) input_start_time: Option<Instant>, // Track when user starts inputting
+    pub(crate) round_start_time: Option<Instant>,
+    keystrokes: Vec<Keystroke>,                   // Everything answered this round, for replays
+    pub publish_events: bool,                     // Whether round events are kept for take_events
+    pub(crate) events: Vec<GameEvent>,
+    pub(crate) input_duration: Option<Duration>,  // Track total input time
+    pub game_mode: GameMode,
+    pub(crate) block_grid: BlockGrid,             // Board used by the spatial span mode
+    pub sequence_length: usize,
+    pub item_display_secs: f32,                   // How long each item is shown
+    pub n_back: NBack,
+    pub dual_n_back: DualNBack,
+    pub complex_span: ComplexSpan,
+    pub test_variant: TestVariant,                // How sequence rounds are tested after presentation
+    pub(crate) probe_index: usize,
+    pub(crate) probe_answer: Option<CharType>,
+    pub(crate) recognition_probe: RecognitionProbe,
+    pub(crate) recognition_answer: Option<bool>,
+    pub(crate) test_scores: TestScores,
+    pub direction_policy: DirectionPolicy,
+    pub alphabet: Alphabet,                       // Characters sequences are drawn from
+    rng: StdRng,                                  // Session generator, each round's seed comes from it
+    pub(crate) round_seed: u64,
+    pub(crate) rounds_started: u32,
+    finished_round: Option<RoundResult>,          // Waiting to be picked up by the front end
+    pub profile: Option<String>,                  // Player name stamped into saved results
+    pub results_path: Option<PathBuf>,            // File finished rounds are appended to
+    pub warning: Option<String>,                  // Problem worth showing, e.g. a failed save
+    pub settings_path: Option<PathBuf>,           // Settings file changes are saved back to
+    pub(crate) saved_settings: Option<Settings>,  // What the settings file holds
+    pub(crate) shown_settings: Option<Settings>,  // In effect last frame, command-line overrides included
+    pub profiles: Option<ProfileStore>,
+    pub(crate) history: Vec<RoundResult>,         // Rounds played by the active profile
+    pub show_profiles: bool,                      // Profile start screen instead of the game
+    pub leaderboard: Leaderboard,
+    pub leaderboard_server: Option<String>,
+    pub(crate) challenge: Option<Challenge>,
+    pub(crate) settings_before_challenge: Option<(Settings, TestVariant)>,
+    pub(crate) daily_attempts: Vec<String>,       // Dates of daily challenges started by the player
+    pub(crate) daily_counts: bool,                // The daily round being played is the first attempt
+    pub(crate) hot_seat: Option<HotSeat>,
+    pub(crate) race: Option<Race>,
+    pub(crate) broadcast: Option<Broadcast>,      // Viewers watching this game
+    pub(crate) viewer: Option<Viewer>,            // Set when this game only shows someone else's
+    pub(crate) event_feed: Option<EventFeed>,     // WebSocket clients such as stream overlays
+    pub(crate) markers: Option<MarkerStream>,     // UDP markers for lab recordings
+    pub(crate) experimenter: Option<ExperimenterConsole>, // Open in a second window during lab sessions
+    pub(crate) session: Option<SessionInfo>,      // Stamped into every round
+    pub(crate) session_setup: Option<SessionSetup>, // Shown instead of the game until it's filled in
+    pub(crate) consent: Option<String>,           // Shown on the session setup screen
+    pub(crate) study_design: Option<StudyDesign>, // Assigns condition orders to new sessions
+    pub(crate) study: Option<Study>,
+    pub(crate) digit_span: Option<DigitSpan>,     // Clinical battery being run
+    pub(crate) norms: Option<Norms>,              // Reference scores for the battery summary
+    pub(crate) quest: Option<Quest>,              // Threshold being estimated
+}
+
+
+impl MemoryGame {
+    pub fn new() -> Self {
+        Self {
+            sequence: vec![],
+            user_input: vec![],
+            current_index: 0,
+            phase: GamePhase::NotStarted,
+            // show_duration: Duration::from_millis(800), // Just a constant, not used
+            error_message: None,
+            sequence_display_timer: 0.0,
+            current_sequence_index: 0,
+            show_sequence: false,
+            normal_mode: true,
+            input_direction: InputDirection::Forward,
+            input_start_time: None,
+            round_start_time: None,
+            keystrokes: vec![],
+            publish_events: false,
+            events: vec![],
+            input_duration: None,
+            game_mode: GameMode::Sequence,
+            block_grid: BlockGrid::default(),
+            sequence_length: 5,
+            item_display_secs: 0.8,
+            n_back: NBack::new(),
+            dual_n_back: DualNBack::new(),
+            complex_span: ComplexSpan::new(),
+            test_variant: TestVariant::SerialRecall,
+            probe_index: 0,
+            probe_answer: None,
+            recognition_probe: RecognitionProbe::default(),
+            recognition_answer: None,
+            test_scores: TestScores::default(),
+            direction_policy: DirectionPolicy::Random,
+            alphabet: Alphabet::Mixed,
+            rng: StdRng::from_entropy(),
+            round_seed: 0,
+            rounds_started: 0,
+            finished_round: None,
+            profile: None,
+            results_path: None,
+            warning: None,
+            settings_path: None,
+            saved_settings: None,
+            shown_settings: None,
+            profiles: None,
+            history: vec![],
+            show_profiles: false,
+            leaderboard: Leaderboard::default(),
+            leaderboard_server: None,
+            challenge: None,
+            settings_before_challenge: None,
+            daily_attempts: vec![],
+            daily_counts: false,
+            hot_seat: None,
+            race: None,
+            broadcast: None,
+            viewer: None,
+            event_feed: None,
+            markers: None,
+            experimenter: None,
+            session: None,
+            session_setup: None,
+            consent: None,
+            study_design: None,
+            study: None,
+            digit_span: None,
+            norms: None,
+            quest: None,
+        }
+    }
+
+    // Makes the whole session reproducible: the same seed gives the same rounds in order
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn generate_sequence(&mut self, length: usize) {
+        let mut rng = StdRng::seed_from_u64(self.round_seed);
+        self.sequence = Vec::with_capacity(length);
+
+        match self.game_mode {
+            GameMode::Sequence | GameMode::ComplexSpan => {
+                for _ in 0..length {
+                    self.sequence.push(self.alphabet.random_item(&mut rng));
+                }
+                if self.game_mode == GameMode::ComplexSpan {
+                    self.complex_span.generate_equations(&mut rng, length);
+                }
+
+                // Prepare the alternative test phases up front
+                if !self.sequence.is_empty() {
+                    self.probe_index = rng.gen_range(0..self.sequence.len());
+                }
+                self.recognition_probe = RecognitionProbe::random(&mut rng, &self.sequence, &self.alphabet);
+            }
+            GameMode::SpatialSpan => {
+                // New block layout every round, each block lights up at most once
+                self.block_grid = BlockGrid::random(&mut rng, BLOCK_COUNT);
+                let length = length.min(self.block_grid.len());
+                for index in rand::seq::index::sample(&mut rng, self.block_grid.len(), length) {
+                    self.sequence.push(CharType::Block(index as u8));
+                }
+            }
+            GameMode::NBack => {
+                // The stream has its own length setting
+                self.sequence = self.n_back.generate_stream(&mut rng, &self.alphabet);
+            }
+            GameMode::DualNBack => {
+                // Positions are kept by dual_n_back, the sequence holds the characters
+                self.sequence = self.dual_n_back.generate_streams(&mut rng, &self.alphabet);
+            }
+        }
+
+        // Decide input direction according to the policy
+        self.input_direction = self.direction_policy.direction(&mut rng, self.rounds_started);
+        if self.input_direction == InputDirection::Sequencing && !self.game_mode.has_sequencing() {
+            self.input_direction = InputDirection::Forward;
+        }
+    }
+
+    pub fn start_game(&mut self) {
+        // Regular rounds are played with the player's own settings again
+        self.end_challenge();
+        let seed = self.next_seed();
+        self.start_round(seed);
+    }
+
+    pub(crate) fn next_seed(&mut self) -> u64 {
+        self.rng.r#gen()
+    }
+
+    // Starts a round whose items come from the given seed rather than the session
+    pub fn start_round(&mut self, seed: u64) {
+        self.round_seed = seed;
+        self.generate_sequence(self.sequence_length);
+        self.rounds_started += 1;
+        self.finished_round = None;
+        self.user_input.clear();
+        self.current_index = self.sequence.len();
+        self.phase = GamePhase::ShowingSequence;
+        self.error_message = None;
+        self.sequence_display_timer = 0.0;
+        self.current_sequence_index = 0;
+        self.show_sequence = true;
+        self.input_start_time = None;
+        self.round_start_time = Some(Instant::now());
+        self.keystrokes.clear();
+        self.input_duration = None;
+        self.probe_answer = None;
+        self.recognition_answer = None;
+
+        self.emit(GameEvent::RoundStarted { seed });
+        self.emit(GameEvent::PhaseChanged(GamePhase::ShowingSequence));
+        if !self.sequence.is_empty() {
+            self.emit(GameEvent::ItemShown(0));
+        }
+    }
+
+    // Routes a typed character to whatever it means in the current mode and phase
+    pub fn press_char(&mut self, c: char) {
+        let c = c.to_ascii_uppercase();
+
+        // Equations shown between complex span items are answered with T and F
+        if self.game_mode == GameMode::ComplexSpan
+            && matches!(self.phase, GamePhase::ShowingSequence) {
+            match c {
+                'T' => self.answer_equation(true),
+                'F' => self.answer_equation(false),
+                _ => {}
+            }
+            return;
+        }
+
+        // Recognition is answered with Y for same and N for different
+        if matches!(self.phase, GamePhase::Recognition) {
+            match c {
+                'Y' => self.answer_recognition(true),
+                'N' => self.answer_recognition(false),
+                _ => {}
+            }
+            return;
+        }
+
+        // Dual n-back uses A for position matches and L for character matches
+        if self.game_mode == GameMode::DualNBack {
+            match c {
+                'A' => self.respond_dual_match(MatchStream::Position),
+                'L' => self.respond_dual_match(MatchStream::Character),
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(input) = CharType::from_char(c) {
+            self.process_input(input);
+        }
+    }
+
+    pub fn process_input(&mut self, input: CharType) {
+        self.log(Action::Item(input.clone()));
+
+        // A probe is answered by a single keystroke
+        if matches!(self.phase, GamePhase::ProbeRecall) {
+            self.answer_probe(input);
+            return;
+        }
+
+        // Only process input when in Inputting phase
+        if !matches!(self.phase, GamePhase::Inputting) {
+            return;
+        }
+
+        // Typed characters only count in sequence rounds, block clicks only in spatial ones
+        if matches!(input, CharType::Block(_)) != (self.game_mode == GameMode::SpatialSpan) {
+            return;
+        }
+
+        self.user_input.push(input.clone());
+
+        // Check if the input matches the expected character based on direction
+        let expected = self.expected_input();
+        let expected_index = self.user_input.len() - 1;
+
+        if expected_index < expected.len() &&
+           expected[expected_index] != input {
+            if self.normal_mode {
+                // In Normal mode, just record the error but continue
+                // Don't show the error message immediately - only show when sequence is complete
+            } else {
+                // In Strict mode, end the game immediately
+                self.error_message = Some(format!(
+                    "Wrong! Expected '{}' but got '{}'",
+                    expected[expected_index].as_char(),
+                    input.as_char()
+                ));
+                self.end_round(GamePhase::GameOver);
+                return;
+            }
+        }
+
+        // Check if the sequence is complete
+        if self.user_input.len() == self.sequence.len() {
+            // Record the total input time
+            if let Some(start_time) = self.input_start_time {
+                self.input_duration = Some(start_time.elapsed());
+            }
+
+            // Check if all characters were correct
+            let mut all_correct = true;
+
+            for (i, user_char) in self.user_input.iter().enumerate() {
+                if expected[i] != *user_char {
+                    all_correct = false;
+                    break;
+                }
+            }
+
+            if all_correct && self.game_mode == GameMode::ComplexSpan && !self.complex_span.meets_threshold() {
+                // Recall was right, but the equations were not taken seriously enough for the round to count
+                self.error_message = Some(format!(
+                    "Equation accuracy below {:.0}%, the round does not count",
+                    self.complex_span.accuracy_threshold * 100.0
+                ));
+                self.end_round(GamePhase::GameOver);
+            } else if all_correct {
+                self.end_round(GamePhase::Success);
+            } else {
+                // Show the error message only when the sequence is complete
+                if !self.normal_mode {
+                    // In strict mode, we already have an error message
+                } else {
+                    // In normal mode, show an error message
+                    self.error_message = Some("Sequence is incorrect!".to_string());
+                }
+                self.end_round(GamePhase::GameOver);
+            }
+        }
+    }
+
+    pub fn respond_match(&mut self) {
+        self.log(Action::Match);
+
+        // Match responses only count while the n-back stream is running
+        if self.game_mode != GameMode::NBack || !matches!(self.phase, GamePhase::ShowingSequence) {
+            return;
+        }
+
+        self.n_back.respond(self.current_sequence_index);
+    }
+
+    fn answer_probe(&mut self, input: CharType) {
+        if let Some(start_time) = self.input_start_time {
+            self.input_duration = Some(start_time.elapsed());
+        }
+
+        let expected = &self.sequence[self.probe_index];
+        let correct = *expected == input;
+        self.test_scores.record_probe(correct);
+        self.probe_answer = Some(input.clone());
+
+        if correct {
+            self.end_round(GamePhase::Success);
+        } else {
+            self.error_message = Some(format!(
+                "Item #{} was '{}' but you answered '{}'",
+                self.probe_index + 1,
+                expected.as_char(),
+                input.as_char()
+            ));
+            self.end_round(GamePhase::GameOver);
+        }
+    }
+
+    pub fn answer_recognition(&mut self, answered_same: bool) {
+        self.log(Action::Recognition(answered_same));
+
+        if !matches!(self.phase, GamePhase::Recognition) {
+            return;
+        }
+
+        if let Some(start_time) = self.input_start_time {
+            self.input_duration = Some(start_time.elapsed());
+        }
+
+        let is_match = self.recognition_probe.is_match();
+        self.test_scores.record_recognition(is_match, answered_same);
+        self.recognition_answer = Some(answered_same);
+
+        if is_match == answered_same {
+            self.end_round(GamePhase::Success);
+        } else {
+            self.error_message = Some(if is_match {
+                "The sequences were the same".to_string()
+            } else {
+                "The sequences were different".to_string()
+            });
+            self.end_round(GamePhase::GameOver);
+        }
+    }
+
+    // Phase that follows the presentation, depending on the test variant
+    fn test_phase(&self) -> GamePhase {
+        if self.game_mode != GameMode::Sequence {
+            return GamePhase::Inputting;
+        }
+
+        match self.test_variant {
+            TestVariant::SerialRecall => GamePhase::Inputting,
+            TestVariant::ProbeRecall => GamePhase::ProbeRecall,
+            TestVariant::Recognition => GamePhase::Recognition,
+        }
+    }
+
+    pub fn answer_equation(&mut self, answer: bool) {
+        self.log(Action::Equation(answer));
+
+        if !matches!(self.phase, GamePhase::ShowingSequence) {
+            return;
+        }
+
+        self.complex_span.answer(self.current_sequence_index, answer);
+    }
+
+    // The sequence in the order the input direction asks for
+    pub fn expected_input(&self) -> Vec<CharType> {
+        self.input_direction.arrange(&self.sequence, CharType::as_char)
+    }
+
+    // Which entered items are in their required position
+    pub fn correct_positions(&self) -> Vec<bool> {
+        let expected = self.expected_input();
+        self.user_input
+            .iter()
+            .enumerate()
+            .map(|(i, user_char)| expected.get(i) == Some(user_char))
+            .collect()
+    }
+
+    pub fn respond_dual_match(&mut self, stream: MatchStream) {
+        self.log(Action::DualMatch(stream));
+
+        if !matches!(self.phase, GamePhase::ShowingSequence) {
+            return;
+        }
+
+        self.dual_n_back.respond(stream, self.current_sequence_index);
+    }
+
+    pub fn delete_last(&mut self) {
+        self.log(Action::Delete);
+
+        // Only allow deletion when in Inputting phase
+        if !matches!(self.phase, GamePhase::Inputting) {
+            return;
+        }
+
+        if !self.user_input.is_empty() {
+            self.user_input.pop();
+            // Don't clear error message when deleting in Normal mode
+            // Only clear error if we're in Strict mode and there are no more errors
+            if !self.normal_mode {
+                // In Strict mode, if we've deleted the incorrect character, clear error
+                if !self.user_input.is_empty() {
+                    let expected = self.expected_input();
+                    let expected_index = self.user_input.len() - 1;
+
+                    if expected_index < expected.len() {
+                        let last_char = self.user_input.last().unwrap();
+                        if expected[expected_index] == *last_char {
+                            self.error_message = None;
+                        }
+                    }
+                } else {
+                    self.error_message = None;
+                }
+            }
+        }
+    }
+
+    // Keeps round input in the log, together with the item on screen when it came
+    fn log(&mut self, action: Action) {
+        if !self.in_round() {
+            return;
+        }
+
+        let keystroke = Keystroke {
+            ms: self.round_start_time.map_or(0, |start| start.elapsed().as_millis() as u64),
+            item: matches!(self.phase, GamePhase::ShowingSequence).then_some(self.current_sequence_index),
+            action,
+        };
+        self.emit(GameEvent::Input(keystroke.clone()));
+        self.keystrokes.push(keystroke);
+    }
+
+    fn end_round(&mut self, phase: GamePhase) {
+        self.phase = phase;
+        self.finished_round = Some(RoundResult {
+            timestamp: unix_timestamp(),
+            profile: None,
+            game_mode: self.game_mode,
+            test_variant: self.test_variant,
+            strict: !self.normal_mode,
+            direction: self.input_direction.clone(),
+            seed: self.round_seed,
+            sequence: self.sequence.iter().map(|c| c.as_char()).collect(),
+            input: match (&self.probe_answer, self.recognition_answer) {
+                (Some(answer), _) => answer.as_char().to_string(),
+                (None, Some(answered_same)) => if answered_same { "Y" } else { "N" }.to_string(),
+                (None, None) => self.user_input.iter().map(|c| c.as_char()).collect(),
+            },
+            success: matches!(self.phase, GamePhase::Success),
+            input_duration_ms: self.input_duration.map(|d| d.as_millis() as u64),
+            error: self.error_message.clone(),
+            score: self.score(),
+            alphabet: Some(self.alphabet.name()),
+            n: match self.game_mode {
+                GameMode::NBack => Some(self.n_back.block_n()),
+                GameMode::DualNBack => Some(self.dual_n_back.block_n()),
+                _ => None,
+            },
+            target_rate: match self.game_mode {
+                GameMode::NBack => Some(self.n_back.target_rate),
+                GameMode::DualNBack => Some(self.dual_n_back.target_rate),
+                _ => None,
+            },
+            accuracy_threshold: (self.game_mode == GameMode::ComplexSpan).then_some(self.complex_span.accuracy_threshold),
+            keystrokes: self.keystrokes.clone(),
+            daily: self.challenge.as_ref().and_then(|challenge| challenge.daily).map(daily_date),
+            session: self.session.clone(),
+        });
+
+        self.emit(GameEvent::PhaseChanged(self.phase.clone()));
+        if let Some(result) = self.finished_round.clone() {
+            self.emit(GameEvent::RoundFinished(Box::new(result)));
+        }
+    }
+
+    // Points for the round: each item in place counts, finishing correctly doubles it up
+    pub fn score(&self) -> u32 {
+        const POINTS_PER_ITEM: u32 = 10;
+        let success = matches!(self.phase, GamePhase::Success);
+        let length = self.sequence.len() as u32;
+        let completion_bonus = if success { POINTS_PER_ITEM * length } else { 0 };
+
+        match self.game_mode {
+            GameMode::NBack => {
+                let results = self.n_back.results(&self.sequence);
+                POINTS_PER_ITEM * results.hits.saturating_sub(results.false_alarms) as u32
+            }
+            GameMode::DualNBack => [MatchStream::Position, MatchStream::Character]
+                .into_iter()
+                .map(|stream| {
+                    let results = self.dual_n_back.results(stream, &self.sequence);
+                    POINTS_PER_ITEM * results.hits.saturating_sub(results.false_alarms) as u32
+                })
+                .sum(),
+            // Probe and recognition answers are all or nothing
+            GameMode::Sequence if self.test_variant != TestVariant::SerialRecall => completion_bonus,
+            _ => {
+                let in_place = self.correct_positions().iter().filter(|correct| **correct).count() as u32;
+                POINTS_PER_ITEM * in_place + completion_bonus
+            }
+        }
+    }
+
+    // The result of the round that just ended, handed out once
+    pub fn take_finished_round(&mut self) -> Option<RoundResult> {
+        self.finished_round.take()
+    }
+
+    // Advances the presentation timer, called once per frame by the front end
+    pub fn advance(&mut self, dt: f32) {
+        // Only update the sequence display timer during showing sequence phase
+        if !matches!(self.phase, GamePhase::ShowingSequence) {
+            return;
+        }
+
+        // The presentation waits while a complex span equation is unanswered
+        let waiting_for_equation = self.game_mode == GameMode::ComplexSpan
+            && self.complex_span.pending_equation(self.current_sequence_index).is_some();
+        if !waiting_for_equation {
+            self.sequence_display_timer += dt.min(0.1);
+        }
+
+        // Show each character for item_display_secs seconds
+        let chars_per_second = 1.0 / self.item_display_secs;
+        let previous_index = self.current_sequence_index;
+        self.current_sequence_index = (self.sequence_display_timer * chars_per_second) as usize;
+        if self.current_sequence_index != previous_index && self.current_sequence_index < self.sequence.len() {
+            self.emit(GameEvent::ItemShown(self.current_sequence_index));
+        }
+
+        if self.game_mode == GameMode::ComplexSpan
+            && self.complex_span.pending_equation(self.current_sequence_index).is_some() {
+            // Hold at the start of the next item's slot so it gets its full display time
+            self.sequence_display_timer = self.current_sequence_index as f32 * self.item_display_secs;
+        }
+
+        if self.current_sequence_index >= self.sequence.len() {
+            if self.game_mode.is_continuous() {
+                // Responses were collected during the stream, go straight to the results
+                if self.game_mode == GameMode::DualNBack {
+                    self.dual_n_back.adapt(&self.sequence);
+                }
+                self.end_round(GamePhase::Success);
+            } else {
+                // Finished showing sequence, move to input phase
+                self.phase = self.test_phase();
+                // Start the timer when transitioning to Inputting phase
+                self.input_start_time = Some(Instant::now());
+                self.emit(GameEvent::PhaseChanged(self.phase.clone()));
+            }
+        }
+    }
+
+    // Whether a round is being presented or answered
+    pub fn in_round(&self) -> bool {
+        matches!(
+            self.phase,
+            GamePhase::ShowingSequence | GamePhase::Inputting | GamePhase::ProbeRecall | GamePhase::Recognition
+        )
+    }
+
+    pub fn phase(&self) -> &GamePhase {
+        &self.phase
+    }
+
+    pub fn sequence(&self) -> &[CharType] {
+        &self.sequence
+    }
+
+    pub fn user_input(&self) -> &[CharType] {
+        &self.user_input
+    }
+
+    pub fn current_sequence_index(&self) -> usize {
+        self.current_sequence_index
+    }
+
+    pub fn keystrokes(&self) -> &[Keystroke] {
+        &self.keystrokes
+    }
+
+    pub fn input_direction(&self) -> &InputDirection {
+        &self.input_direction
+    }
+
+    pub fn error_message(&self) -> Option<&str> {
+        self.error_message.as_deref()
+    }
+
+    pub fn input_duration(&self) -> Option<Duration> {
+        self.input_duration
+    }
+
+    pub fn probe_index(&self) -> usize {
+        self.probe_index
+    }
+
+    pub fn recognition_probe(&self) -> &RecognitionProbe {
+        &self.recognition_probe
+    }
+
+    pub fn test_scores(&self) -> &TestScores {
+        &self.test_scores
+    }
+
+    pub fn probe_answer(&self) -> Option<&CharType> {
+        self.probe_answer.as_ref()
+    }
+
+    pub fn recognition_answer(&self) -> Option<bool> {
+        self.recognition_answer
+    }
+
+    pub fn block_grid(&self) -> &BlockGrid {
+        &self.block_grid
+    }
+
+    pub fn sequence_display_timer(&self) -> f32 {
+        self.sequence_display_timer
+    }
+
+    // Takes the last round's results off the screen, e.g. once they belong to another game type
+    pub fn clear_results(&mut self) {
+        if !self.in_round() {
+            self.phase = GamePhase::NotStarted;
+        }
+    }
+}
+
+impl Default for MemoryGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,168 @@
+use rand::Rng;
+
+use crate::alphabet::Alphabet;
+use crate::char_type::CharType;
+
+// Longest stream the settings offer
+pub const MAX_STREAM_LENGTH: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct NBack {
+    pub n: usize,
+    pub stream_length: usize,
+    pub target_rate: f64, // Share of items (after the first n) that repeat the item n steps back
+    block_n: usize,       // N the current stream was generated with
+    responses: Vec<bool>, // Whether the match key was pressed while each item was up
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NBackResults {
+    pub hits: usize,
+    pub misses: usize,
+    pub false_alarms: usize,
+    pub correct_rejections: usize,
+}
+
+impl NBack {
+    pub fn new() -> Self {
+        Self {
+            n: 2,
+            stream_length: 30,
+            target_rate: 0.3,
+            block_n: 2,
+            responses: vec![],
+        }
+    }
+
+    pub fn generate_stream<R: Rng>(&mut self, rng: &mut R, alphabet: &Alphabet) -> Vec<CharType> {
+        let stream = generate_matches(rng, self.n, self.stream_length, self.target_rate, |rng| {
+            alphabet.random_item(rng)
+        });
+        self.block_n = self.n;
+        self.responses = vec![false; stream.len()];
+        stream
+    }
+
+    pub fn respond(&mut self, index: usize) {
+        if let Some(response) = self.responses.get_mut(index) {
+            *response = true;
+        }
+    }
+
+    pub fn block_n(&self) -> usize {
+        self.block_n
+    }
+
+    pub fn has_responded(&self, index: usize) -> bool {
+        self.responses.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn results(&self, stream: &[CharType]) -> NBackResults {
+        score_responses(stream, &self.responses, self.block_n)
+    }
+}
+
+impl Default for NBack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NBackResults {
+    pub fn accuracy(&self) -> f64 {
+        let total = self.hits + self.misses + self.false_alarms + self.correct_rejections;
+        if total == 0 {
+            return 0.0;
+        }
+        (self.hits + self.correct_rejections) as f64 / total as f64
+    }
+
+    // Sensitivity index with the log-linear correction, so perfect rates stay finite
+    pub fn d_prime(&self) -> f64 {
+        let targets = (self.hits + self.misses) as f64;
+        let non_targets = (self.false_alarms + self.correct_rejections) as f64;
+        let hit_rate = (self.hits as f64 + 0.5) / (targets + 1.0);
+        let false_alarm_rate = (self.false_alarms as f64 + 0.5) / (non_targets + 1.0);
+
+        inverse_normal_cdf(hit_rate) - inverse_normal_cdf(false_alarm_rate)
+    }
+}
+
+// Builds a stream where roughly target_rate of the items after the first n repeat the item n back
+pub fn generate_matches<R: Rng, T: Clone + PartialEq>(
+    rng: &mut R,
+    n: usize,
+    length: usize,
+    target_rate: f64,
+    mut random_item: impl FnMut(&mut R) -> T,
+) -> Vec<T> {
+    let mut stream: Vec<T> = Vec::with_capacity(length);
+
+    for i in 0..length {
+        if i >= n && rng.gen_bool(target_rate) {
+            stream.push(stream[i - n].clone());
+            continue;
+        }
+
+        // Non-targets must not repeat the item n back by accident
+        loop {
+            let item = random_item(rng);
+            if i < n || stream[i - n] != item {
+                stream.push(item);
+                break;
+            }
+        }
+    }
+
+    stream
+}
+
+pub fn score_responses<T: PartialEq>(stream: &[T], responses: &[bool], n: usize) -> NBackResults {
+    let mut results = NBackResults::default();
+
+    for (i, responded) in responses.iter().enumerate() {
+        let is_target = i >= n && stream[i] == stream[i - n];
+        match (is_target, *responded) {
+            (true, true) => results.hits += 1,
+            (true, false) => results.misses += 1,
+            (false, true) => results.false_alarms += 1,
+            (false, false) => results.correct_rejections += 1,
+        }
+    }
+
+    results
+}
+
+// Acklam's rational approximation of the standard normal quantile function
+fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -inverse_normal_cdf(1.0 - p)
+    }
+}
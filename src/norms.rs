@@ -0,0 +1,329 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::digit_span::{DigitSpan, DigitSpanStage};
+use crate::memory_game::MemoryGame;
+
+// Reference scores to compare a battery with, from a CSV file such as
+//
+//   measure,age_min,age_max,mean,sd,p10,p25,p50,p75,p90
+//   backward_span,18,29,5.1,1.3,4,4,5,6,7
+//   forward_total,18,29,10.2,2.1,,,,,
+//
+// Measures are forward_, backward_ and sequencing_ followed by span or total, plus total for
+// the whole battery. Age bands include both ends. A row needs a mean and SD, percentile
+// cutoffs (pNN columns, the score at that percentile) or both; cells it doesn't have stay empty.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Norm {
+    pub measure: String,
+    pub age_min: u32,
+    pub age_max: u32,
+    pub mean_sd: Option<(f64, f64)>,
+    pub cutoffs: Vec<(u32, f64)>, // Percentile and score, by percentile
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Percentile {
+    Exact(f64),
+    Below(u32), // Under the lowest cutoff
+    Above(u32), // Over the highest cutoff
+}
+
+impl fmt::Display for Percentile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Percentile::Exact(percentile) => write!(f, "{:.0}", percentile),
+            Percentile::Below(percentile) => write!(f, "<{}", percentile),
+            Percentile::Above(percentile) => write!(f, ">{}", percentile),
+        }
+    }
+}
+
+// Where a raw score stands against its norm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Standing {
+    pub z: Option<f64>,
+    pub percentile: Option<Percentile>,
+}
+
+impl Norm {
+    pub fn standing(&self, raw: f64) -> Standing {
+        let z = self.mean_sd.map(|(mean, sd)| (raw - mean) / sd);
+        // Cutoffs are what the norms give, so they win over the normal approximation
+        let percentile = self.cutoff_percentile(raw).or_else(|| z.map(|z| Percentile::Exact(normal_cdf(z) * 100.0)));
+        Standing { z, percentile }
+    }
+
+    fn cutoff_percentile(&self, raw: f64) -> Option<Percentile> {
+        let (first, last) = (self.cutoffs.first()?, self.cutoffs.last()?);
+        if raw < first.1 {
+            return Some(Percentile::Below(first.0));
+        }
+        if raw > last.1 {
+            return Some(Percentile::Above(last.0));
+        }
+        // Spans are whole numbers and often tie across cutoffs, so a tie takes the highest
+        // percentile reached, otherwise it's interpolated between the cutoffs either side
+        let reached = self.cutoffs.iter().rev().find(|(_, score)| raw >= *score)?;
+        if raw == reached.1 {
+            return Some(Percentile::Exact(reached.0 as f64));
+        }
+        let next = self.cutoffs.iter().find(|(_, score)| *score > raw)?;
+        let fraction = (raw - reached.1) / (next.1 - reached.1);
+        Some(Percentile::Exact(reached.0 as f64 + fraction * (next.0 as f64 - reached.0 as f64)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Norms {
+    pub path: PathBuf,
+    pub norms: Vec<Norm>,
+}
+
+impl Norms {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("memory_game").join("norms.csv"))
+    }
+
+    // None when there's no file; rows that can't be read are left out, with warnings
+    pub fn load(path: &Path) -> Result<Option<(Self, Vec<String>)>, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Norms::from_csv(path, &text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("could not read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn from_csv(path: &Path, text: &str) -> Result<(Self, Vec<String>), String> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, header)) = lines.next() else {
+            return Err(format!("{} is empty", path.display()));
+        };
+        let columns: Vec<String> = header.split(',').map(|column| column.trim().to_ascii_lowercase()).collect();
+        let column = |name: &str| columns.iter().position(|column| column == name);
+        let (Some(measure), Some(age_min), Some(age_max)) = (column("measure"), column("age_min"), column("age_max")) else {
+            return Err(format!("{}: the header needs measure, age_min and age_max columns", path.display()));
+        };
+        let (mean, sd) = (column("mean"), column("sd"));
+        let percentiles: Vec<(usize, u32)> = columns
+            .iter()
+            .enumerate()
+            .filter_map(|(index, column)| {
+                let percentile = column.strip_prefix('p')?.parse::<u32>().ok()?;
+                (1..100).contains(&percentile).then_some((index, percentile))
+            })
+            .collect();
+
+        let mut norms = Norms { path: path.to_path_buf(), norms: vec![] };
+        let mut warnings = vec![];
+        for (number, line) in lines {
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            let cell = |index: Option<usize>| index.and_then(|index| cells.get(index)).copied().filter(|cell| !cell.is_empty());
+            let row = || -> Result<Norm, &str> {
+                let measure = cell(Some(measure)).ok_or("no measure")?.to_ascii_lowercase();
+                let age = |index| cell(Some(index)).and_then(|cell| cell.parse::<u32>().ok()).ok_or("ages must be whole numbers");
+                let (age_min, age_max) = (age(age_min)?, age(age_max)?);
+                if age_min > age_max {
+                    return Err("age_min is above age_max");
+                }
+                let number = |index| cell(index).map(|cell| cell.parse::<f64>().map_err(|_| "scores must be numbers")).transpose();
+                let mean_sd = match (number(mean)?, number(sd)?) {
+                    (Some(_), Some(sd)) if sd <= 0.0 => return Err("the SD must be above 0"),
+                    (Some(mean), Some(sd)) => Some((mean, sd)),
+                    (None, None) => None,
+                    _ => return Err("a mean needs an SD and the other way round"),
+                };
+                let mut cutoffs = vec![];
+                for (index, percentile) in &percentiles {
+                    if let Some(score) = number(Some(*index))? {
+                        cutoffs.push((*percentile, score));
+                    }
+                }
+                cutoffs.sort_by_key(|(percentile, _)| *percentile);
+                if cutoffs.windows(2).any(|pair| pair[1].1 < pair[0].1) {
+                    return Err("cutoffs must not go down as the percentile goes up");
+                }
+                if mean_sd.is_none() && cutoffs.is_empty() {
+                    return Err("no mean and SD or percentile cutoffs");
+                }
+                Ok(Norm { measure, age_min, age_max, mean_sd, cutoffs })
+            };
+            match row() {
+                Ok(norm) => norms.norms.push(norm),
+                Err(e) => warnings.push(format!("{} line {}: {}, left out", path.display(), number + 1, e)),
+            }
+        }
+        Ok((norms, warnings))
+    }
+
+    pub fn find(&self, measure: &str, age: u32) -> Option<&Norm> {
+        self.norms.iter().find(|norm| norm.measure == measure && (norm.age_min..=norm.age_max).contains(&age))
+    }
+}
+
+// A battery score with how it compares to the norms; standing is None without a matching norm
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormedScore {
+    pub measure: String,
+    pub raw: f64,
+    pub standing: Option<Standing>,
+}
+
+// Every measure of the battery so far, the total once it's finished, compared with the norms for the age
+pub fn digit_span_measures(digit_span: &DigitSpan, norms: Option<&Norms>, age: Option<u32>) -> Vec<NormedScore> {
+    let mut measures = vec![];
+    for score in &digit_span.scores {
+        let name = match score.direction.key() {
+            "reverse" => "backward",
+            key => key,
+        };
+        measures.push((format!("{}_span", name), score.longest_span as f64));
+        measures.push((format!("{}_total", name), score.raw_total as f64));
+    }
+    // The battery total only compares with its norm once every direction has been done
+    if digit_span.stage == DigitSpanStage::Finished {
+        measures.push(("total".to_string(), digit_span.scores.iter().map(|score| score.raw_total as f64).sum()));
+    }
+
+    measures
+        .into_iter()
+        .map(|(measure, raw)| {
+            let standing = match (norms, age) {
+                (Some(norms), Some(age)) => norms.find(&measure, age).map(|norm| norm.standing(raw)),
+                _ => None,
+            };
+            NormedScore { measure, raw, standing }
+        })
+        .collect()
+}
+
+// Standard normal CDF, after Abramowitz and Stegun 7.1.26 (error below 1.5e-7)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - polynomial * (-x * x).exp();
+    if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+impl MemoryGame {
+    pub fn set_norms(&mut self, norms: Option<Norms>) {
+        self.norms = norms;
+    }
+
+    pub fn norms(&self) -> Option<&Norms> {
+        self.norms.as_ref()
+    }
+
+    // The participant's age from the session, which norms are looked up by
+    pub fn age(&self) -> Option<u32> {
+        self.session.as_ref().and_then(|session| session.age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digit_span::{DigitSpanProtocol, DirectionScore};
+    use crate::input_direction::InputDirection;
+
+    fn norms(text: &str) -> (Norms, Vec<String>) {
+        Norms::from_csv(Path::new("norms.csv"), text).unwrap()
+    }
+
+    fn cutoffs(cutoffs: &[(u32, f64)]) -> Norm {
+        Norm { measure: "forward_span".to_string(), age_min: 18, age_max: 29, mean_sd: None, cutoffs: cutoffs.to_vec() }
+    }
+
+    #[test]
+    fn rows_that_cant_be_read_are_left_out_with_a_warning() {
+        let (norms, warnings) = norms(
+            "measure,age_min,age_max,mean,sd,p10,p50,p90\n\
+             forward_span,18,29,6.0,1.0,,,\n\
+             backward_span,18,29,,,3,5,7\n\
+             forward_total,18,29,10,,,,\n\
+             backward_total,18,29,,,4,6,5\n\
+             total,30,18,20,3,,,\n\
+             sequencing_span,18,29,five,1,,,\n\
+             sequencing_total,18,29,10,0,,,\n\
+             forward_span,x,29,6,1,,,\n\
+             backward_span,30,39,,,,,\n",
+        );
+
+        let measures: Vec<&str> = norms.norms.iter().map(|norm| norm.measure.as_str()).collect();
+        assert_eq!(measures, ["forward_span", "backward_span"]);
+        assert_eq!(norms.norms[0].mean_sd, Some((6.0, 1.0)));
+        assert_eq!(norms.norms[1].cutoffs, [(10, 3.0), (50, 5.0), (90, 7.0)]);
+
+        let reasons = [
+            "line 4: a mean needs an SD",
+            "line 5: cutoffs must not go down",
+            "line 6: age_min is above age_max",
+            "line 7: scores must be numbers",
+            "line 8: the SD must be above 0",
+            "line 9: ages must be whole numbers",
+            "line 10: no mean and SD or percentile cutoffs",
+        ];
+        assert_eq!(warnings.len(), reasons.len());
+        for (warning, reason) in warnings.iter().zip(reasons) {
+            assert!(warning.contains(reason), "{}", warning);
+        }
+    }
+
+    #[test]
+    fn a_file_without_the_needed_columns_is_an_error() {
+        assert!(Norms::from_csv(Path::new("norms.csv"), "").is_err());
+        assert!(Norms::from_csv(Path::new("norms.csv"), "measure,age,mean,sd\n").is_err());
+    }
+
+    #[test]
+    fn ties_take_the_highest_percentile_reached() {
+        let norm = cutoffs(&[(10, 4.0), (25, 5.0), (50, 5.0), (75, 6.0)]);
+        assert_eq!(norm.cutoff_percentile(5.0), Some(Percentile::Exact(50.0)));
+        assert_eq!(norm.cutoff_percentile(4.0), Some(Percentile::Exact(10.0)));
+        assert_eq!(norm.cutoff_percentile(6.0), Some(Percentile::Exact(75.0)));
+    }
+
+    #[test]
+    fn scores_outside_the_cutoffs_are_below_or_above() {
+        let norm = cutoffs(&[(10, 4.0), (90, 8.0)]);
+        assert_eq!(norm.cutoff_percentile(3.0), Some(Percentile::Below(10)));
+        assert_eq!(norm.cutoff_percentile(9.0), Some(Percentile::Above(90)));
+        assert_eq!(cutoffs(&[]).cutoff_percentile(5.0), None);
+    }
+
+    #[test]
+    fn scores_between_cutoffs_are_interpolated() {
+        let norm = cutoffs(&[(10, 4.0), (50, 6.0), (90, 10.0)]);
+        assert_eq!(norm.cutoff_percentile(5.0), Some(Percentile::Exact(30.0)));
+        assert_eq!(norm.cutoff_percentile(9.0), Some(Percentile::Exact(80.0)));
+    }
+
+    #[test]
+    fn the_total_waits_for_the_whole_battery() {
+        let (norms, _) = norms("measure,age_min,age_max,mean,sd\nforward_total,18,29,10,2\ntotal,18,29,20,4\n");
+        let mut game = MemoryGame::new();
+        game.start_digit_span(DigitSpanProtocol::default()).unwrap();
+        let digit_span = game.digit_span.as_mut().unwrap();
+        digit_span.scores.push(DirectionScore {
+            direction: InputDirection::Forward,
+            longest_span: 6,
+            raw_total: 10,
+            trials: vec![],
+        });
+        digit_span.stage = DigitSpanStage::Instructions;
+
+        let measures = |digit_span: &DigitSpan| -> Vec<String> {
+            digit_span_measures(digit_span, Some(&norms), Some(20)).into_iter().map(|score| score.measure).collect()
+        };
+        assert_eq!(measures(digit_span), ["forward_span", "forward_total"]);
+
+        digit_span.stage = DigitSpanStage::Finished;
+        assert_eq!(measures(digit_span), ["forward_span", "forward_total", "total"]);
+        let total = digit_span_measures(digit_span, Some(&norms), Some(20)).pop().unwrap();
+        assert_eq!(total.standing.unwrap().z, Some(-2.5));
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::leaderboard::compare;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::round_result::RoundResult;
+use crate::settings::Settings;
+
+const MAX_NAME_LENGTH: usize = 32;
+
+// Player profiles, each a directory holding its own settings and round history
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    root: PathBuf,
+}
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("profile name can't be empty".to_string());
+    }
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(format!("profile name can be at most {} characters", MAX_NAME_LENGTH));
+    }
+    // Names double as directory names
+    if name.starts_with('.')
+        || !name.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' || c == '.') {
+        return Err("profile name can only use letters, digits, spaces, '-', '_' and '.'".to_string());
+    }
+    Ok(())
+}
+
+impl ProfileStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    // Next to settings.toml in the config dir
+    pub fn default_location() -> Option<Self> {
+        dirs::config_dir().map(|dir| ProfileStore::new(dir.join("memory_game")))
+    }
+
+    fn profile_dir(&self, name: &str) -> PathBuf {
+        self.root.join("profiles").join(name)
+    }
+
+    pub fn settings_path(&self, name: &str) -> PathBuf {
+        self.profile_dir(name).join("settings.toml")
+    }
+
+    pub fn history_path(&self, name: &str) -> PathBuf {
+        self.profile_dir(name).join("history.jsonl")
+    }
+
+    // Dates of daily challenges the player has started, one per line
+    fn daily_attempts_path(&self, name: &str) -> PathBuf {
+        self.profile_dir(name).join("daily_attempts")
+    }
+
+    // Where the numbers behind the analytics charts are exported to
+    pub fn analytics_dir(&self, name: &str) -> PathBuf {
+        self.profile_dir(name).join("analytics")
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.root.join("profiles"))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    // Only valid names can be profiles, so "" or ".." never reach the directories above
+    pub fn exists(&self, name: &str) -> bool {
+        validate_name(name).is_ok() && self.profile_dir(name).is_dir()
+    }
+
+    pub fn create(&self, name: &str) -> Result<(), String> {
+        validate_name(name)?;
+        if self.exists(name) {
+            return Err(format!("a profile called '{}' already exists", name));
+        }
+        fs::create_dir_all(self.profile_dir(name))
+            .map_err(|e| format!("could not create profile '{}': {}", name, e))
+    }
+
+    pub fn rename(&self, old: &str, new: &str) -> Result<(), String> {
+        validate_name(old)?;
+        validate_name(new)?;
+        if !self.exists(old) {
+            return Err(format!("there is no profile called '{}'", old));
+        }
+        if self.exists(new) {
+            return Err(format!("a profile called '{}' already exists", new));
+        }
+        fs::rename(self.profile_dir(old), self.profile_dir(new))
+            .map_err(|e| format!("could not rename profile '{}': {}", old, e))?;
+
+        if self.active().as_deref() == Some(old) {
+            self.set_active(Some(new));
+        }
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        validate_name(name)?;
+        if !self.exists(name) {
+            return Err(format!("there is no profile called '{}'", name));
+        }
+        fs::remove_dir_all(self.profile_dir(name))
+            .map_err(|e| format!("could not delete profile '{}': {}", name, e))?;
+
+        if self.active().as_deref() == Some(name) {
+            self.set_active(None);
+        }
+        Ok(())
+    }
+
+    // The profile that was in use when the game last closed
+    pub fn active(&self) -> Option<String> {
+        let name = fs::read_to_string(self.root.join("active_profile")).ok()?;
+        let name = name.trim().to_string();
+        self.exists(&name).then_some(name)
+    }
+
+    pub fn set_active(&self, name: Option<&str>) {
+        let path = self.root.join("active_profile");
+        // Losing track of the last profile only means asking again next time
+        let _ = match name {
+            Some(name) => fs::create_dir_all(&self.root).and_then(|_| fs::write(&path, name)),
+            None => fs::remove_file(&path),
+        };
+    }
+
+    // Lines that don't parse are skipped rather than losing the whole history
+    pub fn load_history(&self, name: &str) -> io::Result<Vec<RoundResult>> {
+        match fs::read_to_string(self.history_path(name)) {
+            Ok(text) => Ok(text
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn load_daily_attempts(&self, name: &str) -> io::Result<Vec<String>> {
+        match fs::read_to_string(self.daily_attempts_path(name)) {
+            Ok(text) => Ok(text.lines().map(str::to_string).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn record_daily_attempt(&self, name: &str, date: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(self.daily_attempts_path(name))?;
+        writeln!(file, "{}", date)
+    }
+}
+
+// Best correct round per game type, in the leaderboard's order
+pub fn personal_bests(history: &[RoundResult]) -> BTreeMap<&str, &RoundResult> {
+    let mut bests: BTreeMap<&str, &RoundResult> = BTreeMap::new();
+
+    for result in history.iter().filter(|result| result.success) {
+        let key = result.game_mode.short_name();
+        let better = bests.get(key).is_none_or(|best| compare(result, best) == Ordering::Less);
+        if better {
+            bests.insert(key, result);
+        }
+    }
+
+    bests
+}
+
+impl MemoryGame {
+    // Makes `name` the active profile (None for guest), loading its settings and history
+    pub fn switch_profile(&mut self, name: Option<String>) {
+        let store = self.profiles.clone();
+        let (settings_path, history, mut daily_attempts) = match (&store, &name) {
+            (Some(store), Some(name)) => {
+                let history = store.load_history(name).unwrap_or_else(|e| {
+                    self.warning = Some(format!("Could not read the history of '{}': {}", name, e));
+                    vec![]
+                });
+                let attempts = store.load_daily_attempts(name).unwrap_or_else(|e| {
+                    self.warning = Some(format!("Could not read the daily challenges of '{}': {}", name, e));
+                    vec![]
+                });
+                (Some(store.settings_path(name)), history, attempts)
+            }
+            _ => (Settings::path(), vec![], vec![]),
+        };
+        // Histories from before attempts were kept still count
+        daily_attempts.extend(history.iter().filter_map(|result| result.daily.clone()));
+
+        // The next player shouldn't inherit a challenge or come back to its settings
+        self.challenge = None;
+        self.settings_before_challenge = None;
+
+        let mut settings = Settings::default();
+        if let Some(path) = &settings_path {
+            let (loaded, warnings) = Settings::load(path);
+            settings = loaded;
+            if !warnings.is_empty() {
+                self.warning = Some(warnings.join("\n"));
+            }
+        }
+        settings.apply(self);
+
+        if let Some(store) = &store {
+            store.set_active(name.as_deref());
+        }
+        self.settings_path = settings_path;
+        self.saved_settings = Some(settings);
+        self.shown_settings = None;
+        self.history = history;
+        self.daily_attempts = daily_attempts;
+        self.profile = name;
+        self.phase = GamePhase::NotStarted;
+    }
+
+    // Rounds played by the active profile
+    pub fn history(&self) -> &[RoundResult] {
+        &self.history
+    }
+
+    // Other hot seat players' rounds aren't part of the active profile's history
+    pub fn add_to_history(&mut self, result: RoundResult) {
+        if self.hot_seat.is_none() || (result.profile.is_some() && result.profile == self.profile) {
+            self.history.push(result);
+        }
+    }
+
+    // Writes the settings file when a choice has changed since the last call
+    pub fn save_settings_if_changed(&mut self) {
+        let Some(path) = self.settings_path.clone() else {
+            return;
+        };
+        // A challenge's settings are only borrowed for its round, like a study condition's,
+        // and a viewer's are the watched game's
+        if self.challenge.is_some()
+            || self.study.is_some()
+            || self.digit_span.is_some()
+            || self.quest.is_some()
+            || self.watching() {
+            return;
+        }
+
+        let current = Settings::from_game(self);
+        match &self.shown_settings {
+            // The first call's settings are what was loaded plus any command-line overrides
+            None => self.shown_settings = Some(current),
+            Some(shown) if *shown != current => {
+                // Only what was changed here is saved, command-line overrides are for this session
+                let saved = self.saved_settings.clone().unwrap_or_default().with_changes(shown, &current);
+                if let Err(e) = saved.save(&path) {
+                    self.warning = Some(format!("Could not save settings to {}: {}", path.display(), e));
+                }
+                self.saved_settings = Some(saved);
+                self.shown_settings = Some(current);
+            }
+            Some(_) => {}
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::block_grid::BLOCK_COUNT;
+use crate::game_mode::GameMode;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::recall_test::TestVariant;
+use crate::round_result::RoundResult;
+use crate::settings::Settings;
+
+// Bayesian adaptive threshold estimation in the spirit of QUEST (Watson & Pelli, 1983). The
+// posterior over the threshold is kept on a grid; after every round it's multiplied by the
+// likelihood of the outcome, and the next round gets whichever length or display time leaves
+// the least expected uncertainty (entropy) about the threshold.
+//
+// Both are worked on as a difficulty that goes up: the length itself, or -ln(item time) so a
+// halving of the time is the same step whatever the time. The psychometric function is a
+// logistic in difficulty, placed so that it passes through the target rate at the threshold.
+
+const LAPSE_RATE: f64 = 0.02;      // Slips even well below threshold
+const CREDIBLE_MASS: f64 = 0.95;
+const MAX_TRIALS: usize = 40;      // Stops here even if the precision isn't reached
+const MAX_LENGTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuestParameter {
+    Length, // Items per sequence, at the current display time
+    Speed,  // Display time per item, at the current length
+}
+
+impl QuestParameter {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "length" => Ok(QuestParameter::Length),
+            "speed" => Ok(QuestParameter::Speed),
+            _ => Err(format!("unknown threshold parameter '{}', use length or speed", text)),
+        }
+    }
+
+    fn difficulty(&self, value: f64) -> f64 {
+        match self {
+            QuestParameter::Length => value,
+            QuestParameter::Speed => -(value / 1000.0).ln(),
+        }
+    }
+
+    fn value(&self, difficulty: f64) -> f64 {
+        match self {
+            QuestParameter::Length => difficulty,
+            QuestParameter::Speed => (-difficulty).exp() * 1000.0,
+        }
+    }
+
+    // Values rounds can be played at: whole lengths up to what the mode allows, or display times
+    // in steps of 50 ms
+    fn candidates(&self, max_length: usize) -> Vec<f64> {
+        match self {
+            QuestParameter::Length => (1..=max_length).map(|length| length as f64).collect(),
+            QuestParameter::Speed => (2..=60).map(|step| step as f64 * 50.0).collect(),
+        }
+    }
+
+    // How steeply success falls off past the threshold, per unit of difficulty
+    fn slope(&self) -> f64 {
+        match self {
+            QuestParameter::Length => 1.5,
+            QuestParameter::Speed => 4.0,
+        }
+    }
+
+    // Prior SD, in difficulty units, around the settings the estimate starts from
+    fn prior_sd(&self) -> f64 {
+        match self {
+            QuestParameter::Length => 3.0,
+            QuestParameter::Speed => 0.8,
+        }
+    }
+
+    // Width of the credible interval, in difficulty units, at which the estimate is precise enough:
+    // one item, or about a quarter either way of the display time
+    fn precision(&self) -> f64 {
+        match self {
+            QuestParameter::Length => 1.0,
+            QuestParameter::Speed => 0.5,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuestParameter::Length => "Length",
+            QuestParameter::Speed => "Display time",
+        }
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            QuestParameter::Length => format!("{:.1} items", value),
+            QuestParameter::Speed => format!("{:.0} ms", value),
+        }
+    }
+}
+
+// Choices on the setup screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuestSetup {
+    pub parameter: QuestParameter,
+    pub target: f64,
+}
+
+impl Default for QuestSetup {
+    fn default() -> Self {
+        Self { parameter: QuestParameter::Length, target: 0.75 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuestStage {
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct Quest {
+    pub parameter: QuestParameter,
+    pub target: f64,           // Success rate the threshold is defined by, 0.5 or 0.75
+    pub grid: Vec<f64>,        // Thresholds considered, in difficulty units
+    pub posterior: Vec<f64>,   // Sums to 1 over the grid
+    pub current: f64,          // Value the round being played was set to
+    pub max_length: usize,     // Longest round the game type can play
+    pub trials: Vec<(f64, bool)>,
+    pub stage: QuestStage,
+    settings_before: (Settings, TestVariant),
+}
+
+impl Quest {
+    pub fn new(parameter: QuestParameter, target: f64, start: f64, max_length: usize) -> Self {
+        let candidates = parameter.candidates(max_length);
+        let (low, high) = (
+            parameter.difficulty(candidates[0]).min(parameter.difficulty(candidates[candidates.len() - 1])),
+            parameter.difficulty(candidates[0]).max(parameter.difficulty(candidates[candidates.len() - 1])),
+        );
+        let steps = 400;
+        let grid: Vec<f64> = (0..=steps).map(|i| low + (high - low) * i as f64 / steps as f64).collect();
+
+        let mean = parameter.difficulty(start);
+        let sd = parameter.prior_sd();
+        let mut posterior: Vec<f64> = grid.iter().map(|theta| (-0.5 * ((theta - mean) / sd).powi(2)).exp()).collect();
+        normalize(&mut posterior);
+
+        let mut quest = Self {
+            parameter,
+            target,
+            grid,
+            posterior,
+            current: start,
+            max_length,
+            trials: vec![],
+            stage: QuestStage::Running,
+            settings_before: (Settings::default(), TestVariant::SerialRecall),
+        };
+        quest.current = quest.next_value();
+        quest
+    }
+
+    // Chance of a correct round at this difficulty if the threshold were theta
+    fn success_probability(&self, difficulty: f64, theta: f64) -> f64 {
+        // Offset so the curve passes through the target at the threshold
+        let at_threshold = self.target / (1.0 - LAPSE_RATE);
+        let offset = (at_threshold / (1.0 - at_threshold)).ln();
+        let logistic = 1.0 / (1.0 + (-(self.parameter.slope() * (theta - difficulty) + offset)).exp());
+        (1.0 - LAPSE_RATE) * logistic
+    }
+
+    pub fn update(&mut self, value: f64, success: bool) {
+        let difficulty = self.parameter.difficulty(value);
+        for (i, theta) in self.grid.iter().enumerate() {
+            let p = self.success_probability(difficulty, *theta);
+            self.posterior[i] *= if success { p } else { 1.0 - p };
+        }
+        normalize(&mut self.posterior);
+        self.trials.push((value, success));
+
+        if self.precise_enough() || self.trials.len() >= MAX_TRIALS {
+            self.stage = QuestStage::Finished;
+        } else {
+            self.current = self.next_value();
+        }
+    }
+
+    // The candidate whose outcome is expected to leave the posterior most concentrated
+    fn next_value(&self) -> f64 {
+        let mut best = (f64::INFINITY, self.current);
+        for value in self.parameter.candidates(self.max_length) {
+            let difficulty = self.parameter.difficulty(value);
+            let likelihoods: Vec<f64> = self.grid.iter().map(|theta| self.success_probability(difficulty, *theta)).collect();
+            let mut expected_entropy = 0.0;
+            for success in [true, false] {
+                let mut after: Vec<f64> = self
+                    .posterior
+                    .iter()
+                    .zip(&likelihoods)
+                    .map(|(prior, p)| prior * if success { *p } else { 1.0 - p })
+                    .collect();
+                let chance: f64 = after.iter().sum();
+                if chance <= 0.0 {
+                    continue;
+                }
+                normalize(&mut after);
+                expected_entropy += chance * entropy(&after);
+            }
+            if expected_entropy < best.0 {
+                best = (expected_entropy, value);
+            }
+        }
+        best.1
+    }
+
+    // Posterior mean, in the parameter's own units
+    pub fn estimate(&self) -> f64 {
+        let mean: f64 = self.grid.iter().zip(&self.posterior).map(|(theta, p)| theta * p).sum();
+        self.parameter.value(mean)
+    }
+
+    // Central credible interval in difficulty units
+    fn interval_difficulty(&self) -> (f64, f64) {
+        let tail = (1.0 - CREDIBLE_MASS) / 2.0;
+        let mut cumulative = 0.0;
+        let (mut low, mut high) = (self.grid[0], self.grid[self.grid.len() - 1]);
+        let mut found_low = false;
+        for (theta, p) in self.grid.iter().zip(&self.posterior) {
+            cumulative += p;
+            if !found_low && cumulative >= tail {
+                low = *theta;
+                found_low = true;
+            }
+            if cumulative >= 1.0 - tail {
+                high = *theta;
+                break;
+            }
+        }
+        (low, high)
+    }
+
+    // Central 95% credible interval in the parameter's units, lower value first
+    pub fn credible_interval(&self) -> (f64, f64) {
+        let (low, high) = self.interval_difficulty();
+        let (a, b) = (self.parameter.value(low), self.parameter.value(high));
+        (a.min(b), a.max(b))
+    }
+
+    pub fn precise_enough(&self) -> bool {
+        let (low, high) = self.interval_difficulty();
+        high - low <= self.parameter.precision()
+    }
+
+    // The posterior with the threshold in the parameter's units, for plotting
+    pub fn density(&self) -> Vec<(f64, f64)> {
+        self.grid.iter().zip(&self.posterior).map(|(theta, p)| (self.parameter.value(*theta), *p)).collect()
+    }
+}
+
+fn normalize(weights: &mut [f64]) {
+    let total: f64 = weights.iter().sum();
+    if total > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= total);
+    }
+}
+
+fn entropy(weights: &[f64]) -> f64 {
+    weights.iter().filter(|p| **p > 0.0).map(|p| -p * p.ln()).sum()
+}
+
+impl MemoryGame {
+    // Estimates the threshold for the current game type, starting from the current settings
+    pub fn start_quest(&mut self, parameter: QuestParameter, target: f64) -> Result<(), String> {
+        if matches!(self.game_mode, GameMode::NBack | GameMode::DualNBack) {
+            return Err("thresholds are for span modes, not n-back".to_string());
+        }
+        if !(0.05..=0.95).contains(&target) {
+            return Err("the target success rate must be between 5% and 95%".to_string());
+        }
+        self.end_challenge();
+        // Spatial span has only so many blocks to light up
+        let max_length = match self.game_mode {
+            GameMode::SpatialSpan => BLOCK_COUNT,
+            _ => MAX_LENGTH,
+        };
+        let start = match parameter {
+            QuestParameter::Length => self.sequence_length.min(max_length) as f64,
+            QuestParameter::Speed => self.item_display_secs as f64 * 1000.0,
+        };
+        let mut quest = Quest::new(parameter, target, start, max_length);
+        quest.settings_before = (Settings::from_game(self), self.test_variant);
+        self.quest = Some(quest);
+        self.phase = GamePhase::NotStarted;
+        Ok(())
+    }
+
+    pub fn quest(&self) -> Option<&Quest> {
+        self.quest.as_ref()
+    }
+
+    pub fn record_quest_trial(&mut self, result: &RoundResult) {
+        if let Some(quest) = &mut self.quest
+            && quest.stage == QuestStage::Running {
+            // The length the round really had, in case the game couldn't make it as long as asked
+            let value = match quest.parameter {
+                QuestParameter::Length => result.length() as f64,
+                QuestParameter::Speed => quest.current,
+            };
+            quest.update(value, result.success);
+        }
+    }
+
+    pub fn next_quest_trial(&mut self) {
+        let Some(quest) = &self.quest else {
+            return;
+        };
+        if quest.stage == QuestStage::Finished {
+            return;
+        }
+        match quest.parameter {
+            QuestParameter::Length => self.sequence_length = quest.current as usize,
+            QuestParameter::Speed => self.item_display_secs = quest.current as f32 / 1000.0,
+        }
+        let seed = self.next_seed();
+        self.start_round(seed);
+    }
+
+    // Back to the settings from before the estimate
+    pub fn leave_quest(&mut self) {
+        if let Some(quest) = self.quest.take() {
+            let (settings, variant) = quest.settings_before;
+            settings.apply(self);
+            self.test_variant = variant;
+        }
+        self.phase = GamePhase::NotStarted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_gets_less_likely_as_rounds_get_harder() {
+        for parameter in [QuestParameter::Length, QuestParameter::Speed] {
+            let quest = Quest::new(parameter, 0.75, 5.0, MAX_LENGTH);
+            let difficulties: Vec<f64> = (0..=40).map(|step| step as f64 * 0.5 - 5.0).collect();
+            for theta in [-2.0, 0.0, 5.0] {
+                let chances: Vec<f64> = difficulties.iter().map(|difficulty| quest.success_probability(*difficulty, theta)).collect();
+                // Far from the threshold the curve flattens out to the lapse rate and to 0
+                assert!(chances.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", chances);
+                assert!(chances[0] > chances[chances.len() - 1]);
+                assert!(chances.iter().all(|p| *p > 0.0 && *p <= 1.0 - LAPSE_RATE));
+                // At the threshold the curve passes through the target
+                assert!((quest.success_probability(theta, theta) - 0.75).abs() < 1e-9);
+            }
+            // And more likely the higher the threshold
+            assert!(quest.success_probability(3.0, 4.0) > quest.success_probability(3.0, 3.0));
+        }
+    }
+
+    #[test]
+    fn the_estimate_stops_once_it_is_precise_enough() {
+        // Someone who gets up to 6 items right and nothing longer
+        let mut quest = Quest::new(QuestParameter::Length, 0.75, 4.0, MAX_LENGTH);
+        while quest.stage == QuestStage::Running {
+            let value = quest.current;
+            quest.update(value, value <= 6.0);
+        }
+
+        assert!(quest.precise_enough());
+        assert!(quest.trials.len() < MAX_TRIALS);
+        assert!((5.0..=7.0).contains(&quest.estimate()), "{}", quest.estimate());
+        assert!(quest.trials.iter().all(|(value, _)| (1.0..=MAX_LENGTH as f64).contains(value)));
+    }
+
+    #[test]
+    fn the_estimate_stops_at_the_trial_limit() {
+        // Correct rounds at the easiest length say next to nothing about the threshold
+        let mut quest = Quest::new(QuestParameter::Length, 0.75, 8.0, MAX_LENGTH);
+        for trial in 1..=MAX_TRIALS {
+            assert_eq!(quest.stage, QuestStage::Running);
+            quest.update(1.0, true);
+            assert_eq!(quest.trials.len(), trial);
+        }
+        assert_eq!(quest.stage, QuestStage::Finished);
+        assert!(!quest.precise_enough());
+    }
+
+    #[test]
+    fn lengths_stay_within_what_the_mode_can_play() {
+        let quest = Quest::new(QuestParameter::Length, 0.5, 12.0, BLOCK_COUNT);
+        assert!(quest.current <= BLOCK_COUNT as f64);
+        assert!(quest.grid.iter().all(|theta| (1.0..=BLOCK_COUNT as f64).contains(theta)));
+    }
+}
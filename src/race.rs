@@ -0,0 +1,425 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::challenge::Challenge;
+use crate::direction_policy::DirectionPolicy;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::recall_test::TestVariant;
+use crate::round_result::RoundResult;
+
+// Head-to-head race between two machines: the host picks the round and decides who won.
+// Both sides send one JSON message per line; only counts of entered items cross the
+// network during a race, never the items themselves.
+
+const PROTOCOL_VERSION: u32 = 2;
+const COUNTDOWN: Duration = Duration::from_secs(3);
+// How long a correct finish waits for the opponent's, in case theirs is faster but still on its way
+const GRACE_MS: u64 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RaceMessage {
+    Hello { version: u32, name: String },
+    Start { challenge: String }, // Challenge code, the countdown starts on arrival
+    Progress { entered: usize },
+    Finished { correct: bool, ms: u64 }, // Time since the sender's round started
+    Outcome { host_won: Option<bool> }, // None when neither entered the sequence correctly
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RaceRole {
+    Host,
+    Joiner,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RaceOutcome {
+    Won,
+    Lost,
+    NoWinner,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RaceStage {
+    Connecting,
+    Lobby, // Connected, waiting for the host to start
+    Countdown(Instant),
+    Racing,
+    Done(RaceOutcome),
+    Disconnected(String),
+}
+
+// Filled in by the network threads, picked up by poll
+#[derive(Debug, Default)]
+struct Inbox {
+    stream: Option<io::Result<TcpStream>>,
+    messages: Vec<RaceMessage>,
+    closed: Option<String>,
+    left: bool, // Tells a host still waiting for an opponent to stop listening
+}
+
+#[derive(Debug, Clone)]
+pub struct Race {
+    pub role: RaceRole,
+    pub address: String,
+    pub stage: RaceStage,
+    pub opponent_name: Option<String>,
+    pub opponent_progress: usize,
+    pub opponent_finished: Option<bool>, // Whether their entry was correct
+    name: String,
+    stream: Option<Arc<TcpStream>>,
+    inbox: Arc<Mutex<Inbox>>,
+    challenge: Option<Challenge>,
+    finished: Option<bool>,
+    finish_ms: u64,
+    opponent_ms: u64,
+    started: Option<Instant>, // When the player's own round of the race started
+    sent_progress: usize,
+}
+
+impl Race {
+    // Listens on `address` for one opponent
+    pub fn host(address: &str, name: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("could not listen on {}: {}", address, e))?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        // The address actually bound, so a port of 0 gets its real number
+        let bound = listener.local_addr().map(|address| address.to_string()).unwrap_or_else(|_| address.to_string());
+        let race = Race::new(RaceRole::Host, &bound, name);
+
+        let inbox = race.inbox.clone();
+        thread::spawn(move || {
+            loop {
+                let stream = match listener.accept() {
+                    Ok((stream, _)) => stream.set_nonblocking(false).map(|_| stream),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        if inbox.lock().unwrap().left {
+                            return;
+                        }
+                        thread::sleep(Duration::from_millis(50));
+                        continue;
+                    }
+                    Err(e) => Err(e),
+                };
+                inbox.lock().unwrap().stream = Some(stream);
+                return;
+            }
+        });
+        Ok(race)
+    }
+
+    pub fn join(address: &str, name: &str) -> Self {
+        let race = Race::new(RaceRole::Joiner, address, name);
+
+        let inbox = race.inbox.clone();
+        let address = address.to_string();
+        thread::spawn(move || {
+            let stream = TcpStream::connect(&address);
+            inbox.lock().unwrap().stream = Some(stream);
+        });
+        race
+    }
+
+    fn new(role: RaceRole, address: &str, name: &str) -> Self {
+        Self {
+            role,
+            address: address.to_string(),
+            stage: RaceStage::Connecting,
+            opponent_name: None,
+            opponent_progress: 0,
+            opponent_finished: None,
+            name: name.to_string(),
+            stream: None,
+            inbox: Arc::new(Mutex::new(Inbox::default())),
+            challenge: None,
+            finished: None,
+            finish_ms: 0,
+            opponent_ms: 0,
+            started: None,
+            sent_progress: 0,
+        }
+    }
+
+    // Host only: every race uses a new round made from the host's settings
+    pub fn start(&mut self, game: &mut MemoryGame) -> Result<(), String> {
+        if self.role != RaceRole::Host || !matches!(self.stage, RaceStage::Lobby | RaceStage::Done(_)) {
+            return Ok(());
+        }
+
+        // Races are played with the host's own settings, not the last race's
+        game.end_challenge();
+        let seed = game.next_seed();
+        let mut challenge = Challenge::from_settings(game, seed)?;
+        // Alternating depends on how many rounds each side played before, so let the seed decide
+        if challenge.direction_policy == DirectionPolicy::Alternate {
+            challenge.direction_policy = DirectionPolicy::Random;
+        }
+        // Progress is counted in entered items, so everyone races with serial recall
+        challenge.test_variant = TestVariant::SerialRecall;
+
+        self.send(&RaceMessage::Start { challenge: challenge.to_code()? });
+        self.begin_countdown(challenge);
+        Ok(())
+    }
+
+    fn begin_countdown(&mut self, challenge: Challenge) {
+        self.challenge = Some(challenge);
+        self.opponent_progress = 0;
+        self.opponent_finished = None;
+        self.finished = None;
+        self.started = None;
+        self.sent_progress = 0;
+        self.stage = RaceStage::Countdown(Instant::now() + COUNTDOWN);
+    }
+
+    // Called every frame: handles the network and starts the round when the countdown ends
+    pub fn poll(&mut self, game: &mut MemoryGame) {
+        let (stream, messages, closed) = {
+            let mut inbox = self.inbox.lock().unwrap();
+            (inbox.stream.take(), std::mem::take(&mut inbox.messages), inbox.closed.take())
+        };
+
+        match stream {
+            Some(Ok(stream)) => self.connected(stream),
+            Some(Err(e)) => self.stage = RaceStage::Disconnected(format!("could not connect to {}: {}", self.address, e)),
+            None => {}
+        }
+
+        for message in messages {
+            self.receive(message);
+        }
+
+        if let RaceStage::Countdown(start) = self.stage
+            && Instant::now() >= start
+            && let Some(challenge) = self.challenge.clone() {
+            game.start_challenge(challenge);
+            self.started = Some(Instant::now());
+            self.stage = RaceStage::Racing;
+        }
+
+        // A finish waiting out the grace time is decided without a new message
+        if self.role == RaceRole::Host {
+            self.decide();
+        }
+
+        if self.stage == RaceStage::Racing && self.finished.is_none() {
+            let entered = game.user_input().len();
+            if entered != self.sent_progress {
+                self.sent_progress = entered;
+                self.send(&RaceMessage::Progress { entered });
+            }
+        }
+
+        if let Some(e) = closed
+            && !matches!(self.stage, RaceStage::Disconnected(_)) {
+            self.stage = RaceStage::Disconnected(e);
+        }
+    }
+
+    fn connected(&mut self, stream: TcpStream) {
+        let _ = stream.set_nodelay(true);
+        let reader = match stream.try_clone() {
+            Ok(reader) => reader,
+            Err(e) => {
+                self.stage = RaceStage::Disconnected(e.to_string());
+                return;
+            }
+        };
+
+        let inbox = self.inbox.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let message = line
+                    .map_err(|e| e.to_string())
+                    .and_then(|line| serde_json::from_str(&line).map_err(|e| format!("unexpected message: {}", e)));
+                match message {
+                    Ok(message) => inbox.lock().unwrap().messages.push(message),
+                    Err(e) => {
+                        inbox.lock().unwrap().closed = Some(e);
+                        return;
+                    }
+                }
+            }
+            inbox.lock().unwrap().closed = Some("the opponent left".to_string());
+        });
+
+        self.stream = Some(Arc::new(stream));
+        self.stage = RaceStage::Lobby;
+        self.send(&RaceMessage::Hello { version: PROTOCOL_VERSION, name: self.name.clone() });
+    }
+
+    fn receive(&mut self, message: RaceMessage) {
+        match message {
+            RaceMessage::Hello { version, name } => {
+                if version != PROTOCOL_VERSION {
+                    self.stage = RaceStage::Disconnected(format!("the opponent's game speaks race protocol {}", version));
+                    self.stream = None;
+                    return;
+                }
+                self.opponent_name = Some(name);
+            }
+            RaceMessage::Start { challenge } => {
+                if self.role != RaceRole::Joiner {
+                    return;
+                }
+                match Challenge::from_code(&challenge) {
+                    Ok(challenge) => self.begin_countdown(challenge),
+                    Err(e) => self.stage = RaceStage::Disconnected(format!("the host sent a bad round: {}", e)),
+                }
+            }
+            RaceMessage::Progress { entered } => self.opponent_progress = entered,
+            RaceMessage::Finished { correct, ms } => {
+                self.opponent_finished = Some(correct);
+                self.opponent_ms = ms;
+                if self.role == RaceRole::Host {
+                    self.decide();
+                }
+            }
+            RaceMessage::Outcome { host_won } => {
+                if self.role != RaceRole::Joiner {
+                    return;
+                }
+                self.stage = RaceStage::Done(match host_won {
+                    Some(false) => RaceOutcome::Won,
+                    Some(true) => RaceOutcome::Lost,
+                    None => RaceOutcome::NoWinner,
+                });
+            }
+        }
+    }
+
+    // The player's own round ended
+    pub fn finish(&mut self, result: &RoundResult) {
+        if self.stage != RaceStage::Racing || self.finished.is_some() {
+            return;
+        }
+        self.finished = Some(result.success);
+        self.finish_ms = self.elapsed_ms();
+        self.send(&RaceMessage::Progress { entered: result.input.chars().count() });
+        self.send(&RaceMessage::Finished { correct: result.success, ms: self.finish_ms });
+        if self.role == RaceRole::Host {
+            self.decide();
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.map_or(0, |started| started.elapsed().as_millis() as u64)
+    }
+
+    // Host only: the faster correct entry wins, a wrong one leaves the race to the other. Each
+    // side times itself from the start of its own round, so the network delay doesn't favour the
+    // host; a correct finish waits GRACE_MS for a faster one that may still be on its way.
+    fn decide(&mut self) {
+        if self.stage != RaceStage::Racing {
+            return;
+        }
+
+        let elapsed = self.elapsed_ms();
+        let own = self.finished.map(|correct| (correct, self.finish_ms));
+        let theirs = self.opponent_finished.map(|correct| (correct, self.opponent_ms));
+        let outcome = match (own, theirs) {
+            (Some((true, mine)), Some((true, other))) if mine < other => RaceOutcome::Won,
+            (Some((true, mine)), Some((true, other))) if other < mine => RaceOutcome::Lost,
+            (Some((true, _)), Some((true, _))) => RaceOutcome::NoWinner, // A dead heat
+            (Some((true, _)), Some((false, _))) => RaceOutcome::Won,
+            (Some((false, _)), Some((true, _))) => RaceOutcome::Lost,
+            (Some((false, _)), Some((false, _))) => RaceOutcome::NoWinner,
+            (Some((true, mine)), None) if elapsed >= mine + GRACE_MS => RaceOutcome::Won,
+            // Still typing past the opponent's time, so the host can't be faster any more
+            (None, Some((true, other))) if elapsed >= other => RaceOutcome::Lost,
+            _ => return,
+        };
+        let host_won = match outcome {
+            RaceOutcome::Won => Some(true),
+            RaceOutcome::Lost => Some(false),
+            RaceOutcome::NoWinner => None,
+        };
+        self.send(&RaceMessage::Outcome { host_won });
+        self.stage = RaceStage::Done(outcome);
+    }
+
+    fn send(&mut self, message: &RaceMessage) {
+        let Some(stream) = &self.stream else {
+            return;
+        };
+        let line = serde_json::to_string(message).unwrap_or_default();
+        if let Err(e) = writeln!(stream.as_ref(), "{}", line) {
+            self.stage = RaceStage::Disconnected(format!("lost the connection: {}", e));
+            self.stream = None;
+        }
+    }
+
+    // Seconds left in the countdown, rounded up
+    pub fn countdown(&self) -> Option<u64> {
+        match self.stage {
+            RaceStage::Countdown(start) => Some(start.saturating_duration_since(Instant::now()).as_secs() + 1),
+            _ => None,
+        }
+    }
+
+    // Whether the player's own round is over and the race waits for the opponent
+    pub fn waiting_for_opponent(&self, game: &MemoryGame) -> bool {
+        self.stage == RaceStage::Racing && matches!(game.phase(), GamePhase::Success | GamePhase::GameOver)
+    }
+
+    pub fn leave(&mut self) {
+        self.inbox.lock().unwrap().left = true;
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+impl MemoryGame {
+    fn race_name(&self) -> String {
+        self.profile.clone().unwrap_or_else(|| "Guest".to_string())
+    }
+
+    pub fn host_race(&mut self, address: &str) -> Result<(), String> {
+        self.race = Some(Race::host(address, &self.race_name())?);
+        Ok(())
+    }
+
+    pub fn join_race(&mut self, address: &str) {
+        self.race = Some(Race::join(address, &self.race_name()));
+    }
+
+    pub fn start_race(&mut self) -> Result<(), String> {
+        let Some(mut race) = self.race.take() else {
+            return Ok(());
+        };
+        let started = race.start(self);
+        self.race = Some(race);
+        started
+    }
+
+    // The player's round of the race ended
+    pub fn finish_race(&mut self, result: &RoundResult) {
+        if let Some(race) = &mut self.race {
+            race.finish(result);
+        }
+    }
+
+    pub fn poll_race(&mut self) {
+        if let Some(mut race) = self.race.take() {
+            race.poll(self);
+            self.race = Some(race);
+        }
+    }
+
+    pub fn leave_race(&mut self) {
+        if let Some(race) = &mut self.race {
+            race.leave();
+        }
+        self.race = None;
+        self.end_challenge();
+        self.phase = GamePhase::NotStarted;
+    }
+
+    pub fn race(&self) -> Option<&Race> {
+        self.race.as_ref()
+    }
+}
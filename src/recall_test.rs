@@ -0,0 +1,127 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::char_type::CharType;
+use crate::n_back::NBackResults;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TestVariant {
+    SerialRecall,
+    ProbeRecall,
+    Recognition,
+}
+
+impl TestVariant {
+    pub const ALL: [TestVariant; 3] = [
+        TestVariant::SerialRecall,
+        TestVariant::ProbeRecall,
+        TestVariant::Recognition,
+    ];
+
+    pub fn short_name(&self) -> &str {
+        match self {
+            TestVariant::SerialRecall => "Serial Recall",
+            TestVariant::ProbeRecall => "Probe Recall",
+            TestVariant::Recognition => "Recognition",
+        }
+    }
+
+    // Name used in settings files and URLs
+    pub fn key(&self) -> &'static str {
+        match self {
+            TestVariant::SerialRecall => "serial-recall",
+            TestVariant::ProbeRecall => "probe-recall",
+            TestVariant::Recognition => "recognition",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        TestVariant::ALL
+            .into_iter()
+            .find(|variant| variant.key() == text.to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown test variant '{}'", text))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FoilKind {
+    SwappedNeighbours,
+    Substituted,
+}
+
+impl FoilKind {
+    pub fn description(&self) -> &str {
+        match self {
+            FoilKind::SwappedNeighbours => "two neighbouring items were swapped",
+            FoilKind::Substituted => "one item was replaced",
+        }
+    }
+}
+
+// Sequence shown in the recognition phase, either the original or a near-miss foil
+#[derive(Debug, Clone, Default)]
+pub struct RecognitionProbe {
+    pub candidate: Vec<CharType>,
+    pub foil: Option<FoilKind>,
+}
+
+impl RecognitionProbe {
+    pub fn random<R: Rng>(rng: &mut R, sequence: &[CharType], alphabet: &Alphabet) -> Self {
+        let mut candidate = sequence.to_vec();
+        if sequence.len() < 2 || rng.gen_bool(0.5) {
+            return Self { candidate, foil: None };
+        }
+
+        // Swapping only makes a different sequence when the neighbours differ
+        let swappable: Vec<usize> = (0..sequence.len() - 1)
+            .filter(|i| sequence[*i] != sequence[i + 1])
+            .collect();
+
+        if !swappable.is_empty() && rng.gen_bool(0.5) {
+            let i = swappable[rng.gen_range(0..swappable.len())];
+            candidate.swap(i, i + 1);
+            Self { candidate, foil: Some(FoilKind::SwappedNeighbours) }
+        } else {
+            let i = rng.gen_range(0..sequence.len());
+            loop {
+                let replacement = alphabet.random_item(rng);
+                if replacement != sequence[i] {
+                    candidate[i] = replacement;
+                    break;
+                }
+            }
+            Self { candidate, foil: Some(FoilKind::Substituted) }
+        }
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.foil.is_none()
+    }
+}
+
+// Running tallies for the alternative test phases over the session
+#[derive(Debug, Clone, Default)]
+pub struct TestScores {
+    pub probe_correct: usize,
+    pub probe_total: usize,
+    pub recognition: NBackResults, // "Same" answers are treated as detections
+}
+
+impl TestScores {
+    pub fn record_probe(&mut self, correct: bool) {
+        self.probe_total += 1;
+        if correct {
+            self.probe_correct += 1;
+        }
+    }
+
+    pub fn record_recognition(&mut self, is_match: bool, answered_same: bool) {
+        match (is_match, answered_same) {
+            (true, true) => self.recognition.hits += 1,
+            (true, false) => self.recognition.misses += 1,
+            (false, true) => self.recognition.false_alarms += 1,
+            (false, false) => self.recognition.correct_rejections += 1,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::char_type::CharType;
+use crate::complex_span::ACCURACY_THRESHOLDS;
+use crate::direction_policy::DirectionPolicy;
+use crate::dual_n_back::MatchStream;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::n_back::MAX_STREAM_LENGTH;
+use crate::round_result::RoundResult;
+
+// Something the player did during a round, at the engine level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Item(CharType),         // Typed character, clicked block or probe answer
+    Delete,
+    Match,                  // N-back match key
+    DualMatch(MatchStream), // Dual n-back A or L
+    Equation(bool),         // Complex span equation judged true or false
+    Recognition(bool),      // Recognition probe judged the same or different
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystroke {
+    pub ms: u64,             // Since the round started
+    pub item: Option<usize>, // Item on screen, None once the presentation was over
+    pub action: Action,
+}
+
+// Safety net for logs that never let the presentation finish
+const MAX_STEPS: usize = 1_000_000;
+
+// Plays a recorded round again under the same rules and returns what the engine makes of it
+pub fn replay(result: &RoundResult) -> Result<RoundResult, String> {
+    let alphabet = result.alphabet.as_deref().ok_or("the round has no alphabet recorded")?;
+
+    let mut game = MemoryGame::new();
+    game.game_mode = result.game_mode;
+    game.test_variant = result.test_variant;
+    game.normal_mode = !result.strict;
+    game.alphabet = Alphabet::parse(alphabet)?;
+    game.direction_policy = DirectionPolicy::fixed(&result.direction);
+    game.sequence_length = result.length();
+    if let Some(n) = result.n {
+        game.n_back.n = n;
+        game.dual_n_back.n = n;
+    }
+    if let Some(target_rate) = result.target_rate {
+        game.n_back.target_rate = target_rate;
+        game.dual_n_back.target_rate = target_rate;
+    }
+    game.n_back.stream_length = result.length();
+    game.dual_n_back.stream_length = result.length();
+    if let Some(accuracy_threshold) = result.accuracy_threshold {
+        game.complex_span.accuracy_threshold = accuracy_threshold;
+    }
+
+    game.start_round(result.seed);
+
+    // Small steps so no item is skipped over
+    let step = (game.item_display_secs / 4.0).min(0.1);
+    let mut steps = 0;
+    let mut wait_until = |game: &mut MemoryGame, item: Option<usize>| -> Result<(), String> {
+        while matches!(game.phase(), GamePhase::ShowingSequence)
+            && item.is_none_or(|item| game.current_sequence_index() < item) {
+            steps += 1;
+            if steps > MAX_STEPS {
+                return Err("the presentation never finished".to_string());
+            }
+            game.advance(step);
+        }
+        Ok(())
+    };
+
+    for keystroke in &result.keystrokes {
+        wait_until(&mut game, keystroke.item)?;
+        match &keystroke.action {
+            Action::Item(item) => game.process_input(item.clone()),
+            Action::Delete => game.delete_last(),
+            Action::Match => game.respond_match(),
+            Action::DualMatch(stream) => game.respond_dual_match(*stream),
+            Action::Equation(answer) => game.answer_equation(*answer),
+            Action::Recognition(answered_same) => game.answer_recognition(*answered_same),
+        }
+    }
+
+    // Continuous modes end when the stream does
+    if game.game_mode.is_continuous() {
+        wait_until(&mut game, None)?;
+    }
+
+    game.take_finished_round()
+        .ok_or_else(|| "the keystrokes don't finish the round".to_string())
+}
+
+// Input time as the keystroke log gives it: from the first answer after the presentation to the last
+pub fn input_duration_ms(keystrokes: &[Keystroke]) -> Option<u64> {
+    let mut answers = keystrokes.iter().filter(|keystroke| keystroke.item.is_none());
+    let first = answers.next()?;
+    let last = answers.next_back().unwrap_or(first);
+    Some(last.ms.saturating_sub(first.ms))
+}
+
+// Settings a round can be replayed under; anything else could make the engine panic
+fn check_settings(result: &RoundResult) -> Result<(), String> {
+    let length = result.length();
+    if length == 0 {
+        return Err("the round has no items".to_string());
+    }
+    if length > MAX_STREAM_LENGTH {
+        return Err(format!("the round has {} items, more than {}", length, MAX_STREAM_LENGTH));
+    }
+    if let Some(n) = result.n
+        && !(1..length).contains(&n) {
+        return Err(format!("N must be from 1 to {}, not {}", length - 1, n));
+    }
+    if let Some(target_rate) = result.target_rate
+        && !(target_rate > 0.0 && target_rate < 1.0) {
+        return Err(format!("the target rate must be between 0 and 1, not {}", target_rate));
+    }
+    if let Some(accuracy_threshold) = result.accuracy_threshold
+        && !ACCURACY_THRESHOLDS.contains(&accuracy_threshold) {
+        return Err(format!(
+            "the accuracy threshold must be from {} to {}, not {}",
+            ACCURACY_THRESHOLDS.start(),
+            ACCURACY_THRESHOLDS.end(),
+            accuracy_threshold
+        ));
+    }
+    Ok(())
+}
+
+// Checks a submitted round against its replay
+pub fn verify(result: &RoundResult) -> Result<(), String> {
+    check_settings(result)?;
+    let replayed = replay(result)?;
+
+    if replayed.sequence != result.sequence {
+        return Err("the sequence doesn't match the seed".to_string());
+    }
+    if replayed.input != result.input {
+        return Err("the keystrokes don't give the submitted input".to_string());
+    }
+    if replayed.success != result.success {
+        return Err("the keystrokes don't give the submitted outcome".to_string());
+    }
+    if replayed.score != result.score {
+        return Err(format!("the keystrokes score {} points, not {}", replayed.score, result.score));
+    }
+
+    Ok(())
+}
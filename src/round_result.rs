@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game_mode::GameMode;
+use crate::input_direction::InputDirection;
+use crate::recall_test::TestVariant;
+use crate::replay::Keystroke;
+use crate::session::SessionInfo;
+
+// One finished round, as written to result files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub timestamp: u64, // Seconds since the Unix epoch
+    pub profile: Option<String>,
+    pub game_mode: GameMode,
+    pub test_variant: TestVariant,
+    pub strict: bool,
+    pub direction: InputDirection,
+    pub seed: u64, // Seed the round's sequence was generated from
+    pub sequence: String,
+    pub input: String,
+    pub success: bool,
+    pub input_duration_ms: Option<u64>,
+    pub error: Option<String>,
+    #[serde(default)] // Older history lines have no score
+    pub score: u32,
+    // What it takes to replay the round; older history lines can't be replayed
+    #[serde(default)]
+    pub alphabet: Option<String>,
+    #[serde(default)]
+    pub n: Option<usize>, // N of n-back rounds
+    #[serde(default)]
+    pub target_rate: Option<f64>, // Share of matches in n-back rounds
+    #[serde(default)]
+    pub accuracy_threshold: Option<f64>, // Equation accuracy a complex span round needed
+    #[serde(default)]
+    pub keystrokes: Vec<Keystroke>,
+    #[serde(default)]
+    pub daily: Option<String>, // Date of the daily challenge this round was
+    #[serde(default)]
+    pub session: Option<SessionInfo>, // Participant and session of a study
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Calendar date (UTC) of a Unix timestamp as YYYY-MM-DD
+pub fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_date(timestamp / 86_400);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 to (year, month, day), after Howard Hinnant's civil_from_days
+pub fn civil_date(days: u64) -> (i64, u32, u32) {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+impl RoundResult {
+    pub fn length(&self) -> usize {
+        self.sequence.chars().count()
+    }
+
+    // Appends the result as one JSON line
+    pub fn append_to(&self, path: &Path) -> io::Result<()> {
+        let line = serde_json::to_string(self).map_err(io::Error::other)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::memory_game::{GamePhase, MemoryGame};
+
+// Study details stamped into every round played in the session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub participant_id: String,
+    pub session: u32,
+    pub condition: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub age: Option<u32>, // In years, for comparing with norms
+}
+
+const MAX_ID_LENGTH: usize = 32;
+const MAX_AGE: u32 = 120;
+
+// Participant IDs end up in file names and spreadsheets, so they're kept plain
+pub fn validate_participant_id(id: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err("enter a participant ID".to_string());
+    }
+    if id.len() > MAX_ID_LENGTH {
+        return Err(format!("participant IDs are at most {} characters", MAX_ID_LENGTH));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("participant IDs may only use letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+// The session-setup screen's fields, as typed
+#[derive(Debug, Clone, Default)]
+pub struct SessionSetup {
+    pub participant_text: String,
+    pub session_text: String,
+    pub condition_text: String,
+    pub notes_text: String,
+    pub age_text: String,
+    pub consent: Option<String>, // Text the participant has to agree to first
+    pub consent_given: bool,
+    pub error: Option<String>,
+}
+
+impl SessionSetup {
+    pub fn new(consent: Option<String>) -> Self {
+        Self {
+            session_text: "1".to_string(),
+            consent,
+            ..Default::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<SessionInfo, String> {
+        let participant_id = self.participant_text.trim();
+        validate_participant_id(participant_id)?;
+
+        let session = match self.session_text.trim().parse::<u32>() {
+            Ok(session) if session > 0 => session,
+            _ => return Err("the session number must be a whole number from 1".to_string()),
+        };
+
+        let age = match self.age_text.trim() {
+            "" => None,
+            text => match text.parse::<u32>() {
+                Ok(age) if (1..=MAX_AGE).contains(&age) => Some(age),
+                _ => return Err(format!("the age must be a whole number of years from 1 to {}", MAX_AGE)),
+            },
+        };
+
+        if self.consent.is_some() && !self.consent_given {
+            return Err("the participant has to agree to the consent text first".to_string());
+        }
+
+        let optional = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+        Ok(SessionInfo {
+            participant_id: participant_id.to_string(),
+            session,
+            condition: optional(&self.condition_text),
+            notes: optional(&self.notes_text),
+            age,
+        })
+    }
+}
+
+pub fn load_consent(path: &Path) -> Result<String, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    if text.trim().is_empty() {
+        return Err(format!("{} is empty", path.display()));
+    }
+    Ok(text)
+}
+
+impl MemoryGame {
+    // Shows the session-setup screen; rounds can't start until it's filled in
+    pub fn open_session_setup(&mut self) {
+        let mut setup = SessionSetup::new(self.consent.clone());
+        if let Some(session) = &self.session {
+            setup.participant_text = session.participant_id.clone();
+            setup.session_text = session.session.to_string();
+            setup.condition_text = session.condition.clone().unwrap_or_default();
+            setup.notes_text = session.notes.clone().unwrap_or_default();
+            setup.age_text = session.age.map(|age| age.to_string()).unwrap_or_default();
+        }
+        self.session_setup = Some(setup);
+        self.phase = GamePhase::NotStarted;
+    }
+
+    pub fn start_session(&mut self, session: SessionInfo) -> Result<(), String> {
+        self.start_study(&session)?;
+        self.session = Some(session);
+        self.session_setup = None;
+        self.phase = GamePhase::NotStarted;
+        Ok(())
+    }
+
+    // Leaves the setup screen, the session already running goes on
+    pub fn close_session_setup(&mut self) {
+        self.session_setup = None;
+    }
+
+    // Text participants agree to on the setup screen from now on
+    pub fn set_consent(&mut self, consent: Option<String>) {
+        self.consent = consent;
+    }
+
+    pub fn session(&self) -> Option<&SessionInfo> {
+        self.session.as_ref()
+    }
+
+    pub fn session_setup(&self) -> Option<&SessionSetup> {
+        self.session_setup.as_ref()
+    }
+
+    pub fn session_setup_mut(&mut self) -> Option<&mut SessionSetup> {
+        self.session_setup.as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> SessionSetup {
+        SessionSetup {
+            participant_text: " P07 ".to_string(),
+            age_text: "34".to_string(),
+            notes_text: "  ".to_string(),
+            ..SessionSetup::new(None)
+        }
+    }
+
+    #[test]
+    fn participant_ids_are_kept_plain() {
+        assert_eq!(validate_participant_id("P07_a-b"), Ok(()));
+        assert!(validate_participant_id("").is_err());
+        assert!(validate_participant_id("P 07").is_err());
+        assert!(validate_participant_id("../P07").is_err());
+        assert!(validate_participant_id(&"p".repeat(MAX_ID_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn filled_in_fields_give_the_session() {
+        let session = setup().validate().unwrap();
+        assert_eq!(session.participant_id, "P07");
+        assert_eq!(session.session, 1);
+        assert_eq!(session.age, Some(34));
+        assert_eq!(session.condition, None);
+        assert_eq!(session.notes, None);
+    }
+
+    #[test]
+    fn sessions_and_ages_must_be_in_range() {
+        for session_text in ["0", "-1", "one"] {
+            assert!(SessionSetup { session_text: session_text.to_string(), ..setup() }.validate().is_err());
+        }
+        for age_text in ["0", "121", "3.5"] {
+            assert!(SessionSetup { age_text: age_text.to_string(), ..setup() }.validate().is_err());
+        }
+        assert_eq!(SessionSetup { age_text: String::new(), ..setup() }.validate().unwrap().age, None);
+    }
+
+    #[test]
+    fn consent_has_to_be_given_when_there_is_a_text() {
+        let consent = SessionSetup { consent: Some("I agree".to_string()), ..setup() };
+        assert!(consent.validate().is_err());
+        assert!(SessionSetup { consent_given: true, ..consent }.validate().is_ok());
+    }
+}
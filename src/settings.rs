@@ -0,0 +1,175 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::alphabet::Alphabet;
+use crate::direction_policy::DirectionPolicy;
+use crate::game_mode::GameMode;
+use crate::memory_game::MemoryGame;
+
+// Choices that survive a restart, stored as TOML in the config dir
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub strict: bool,
+    pub sequence_length: usize,
+    pub item_display_ms: u64,
+    pub direction_policy: DirectionPolicy,
+    pub alphabet: Alphabet,
+    pub game_mode: GameMode,
+    pub leaderboard_server: Option<String>, // host:port of a leaderboard_server
+}
+
+impl Default for Settings {
+    // The defaults are whatever a fresh game starts with
+    fn default() -> Self {
+        Settings::from_game(&MemoryGame::new())
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("memory_game").join("settings.toml"))
+    }
+
+    pub fn from_game(game: &MemoryGame) -> Self {
+        Self {
+            strict: !game.normal_mode,
+            sequence_length: game.sequence_length,
+            item_display_ms: (game.item_display_secs * 1000.0).round() as u64,
+            direction_policy: game.direction_policy,
+            alphabet: game.alphabet.clone(),
+            game_mode: game.game_mode,
+            leaderboard_server: game.leaderboard_server.clone(),
+        }
+    }
+
+    pub fn apply(&self, game: &mut MemoryGame) {
+        game.normal_mode = !self.strict;
+        game.sequence_length = self.sequence_length;
+        game.item_display_secs = self.item_display_ms as f32 / 1000.0;
+        game.direction_policy = self.direction_policy;
+        game.alphabet = self.alphabet.clone();
+        game.game_mode = self.game_mode;
+        game.leaderboard_server = self.leaderboard_server.clone();
+    }
+
+    // These settings with only the choices that changed from `before` to `after` taken over, so
+    // whatever else differs (command-line overrides) stays out of the file
+    pub fn with_changes(&self, before: &Settings, after: &Settings) -> Settings {
+        fn pick<T: PartialEq + Clone>(saved: &T, before: &T, after: &T) -> T {
+            if before != after { after.clone() } else { saved.clone() }
+        }
+        Settings {
+            strict: pick(&self.strict, &before.strict, &after.strict),
+            sequence_length: pick(&self.sequence_length, &before.sequence_length, &after.sequence_length),
+            item_display_ms: pick(&self.item_display_ms, &before.item_display_ms, &after.item_display_ms),
+            direction_policy: pick(&self.direction_policy, &before.direction_policy, &after.direction_policy),
+            alphabet: pick(&self.alphabet, &before.alphabet, &after.alphabet),
+            game_mode: pick(&self.game_mode, &before.game_mode, &after.game_mode),
+            leaderboard_server: pick(&self.leaderboard_server, &before.leaderboard_server, &after.leaderboard_server),
+        }
+    }
+
+    // A missing file gives the defaults; bad fields fall back one by one, with warnings
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        match fs::read_to_string(path) {
+            Ok(text) => Settings::from_toml(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (Settings::default(), vec![]),
+            Err(e) => (
+                Settings::default(),
+                vec![format!("Could not read {}: {}, using default settings", path.display(), e)],
+            ),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> (Self, Vec<String>) {
+        let mut settings = Settings::default();
+        let mut warnings = vec![];
+
+        let table = match text.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                warnings.push(format!("Settings file is not valid TOML ({}), using default settings", e.message()));
+                return (settings, warnings);
+            }
+        };
+
+        let mut missing = vec![];
+        let mut field = |name: &str, read: &mut dyn FnMut(&toml::Value) -> Result<(), String>| {
+            match table.get(name) {
+                Some(value) => {
+                    if let Err(e) = read(value) {
+                        warnings.push(format!("Invalid '{}' in settings ({}), using the default", name, e));
+                    }
+                }
+                None => missing.push(name.to_string()),
+            }
+        };
+
+        field("strict", &mut |value| {
+            settings.strict = value.as_bool().ok_or("expected true or false")?;
+            Ok(())
+        });
+        field("sequence_length", &mut |value| {
+            settings.sequence_length = value
+                .as_integer()
+                .filter(|length| *length >= 1)
+                .ok_or("expected a whole number of at least 1")? as usize;
+            Ok(())
+        });
+        field("item_display_ms", &mut |value| {
+            settings.item_display_ms = value
+                .as_integer()
+                .filter(|millis| *millis >= 1)
+                .ok_or("expected a whole number of at least 1")? as u64;
+            Ok(())
+        });
+        field("direction_policy", &mut |value| {
+            settings.direction_policy = DirectionPolicy::parse(value.as_str().ok_or("expected a string")?)?;
+            Ok(())
+        });
+        field("alphabet", &mut |value| {
+            settings.alphabet = Alphabet::parse(value.as_str().ok_or("expected a string")?)?;
+            Ok(())
+        });
+        field("game_mode", &mut |value| {
+            settings.game_mode = GameMode::parse(value.as_str().ok_or("expected a string")?)?;
+            Ok(())
+        });
+
+        // Left out when there is no server, so not missing
+        if let Some(value) = table.get("leaderboard_server") {
+            match value.as_str() {
+                Some(address) => settings.leaderboard_server = Some(address.to_string()),
+                None => warnings.push("Invalid 'leaderboard_server' in settings (expected a string), using none".to_string()),
+            }
+        }
+
+        if !missing.is_empty() {
+            warnings.push(format!("Settings file has no {}, using the defaults", missing.join(", ")));
+        }
+
+        (settings, warnings)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut table = toml::Table::new();
+        table.insert("strict".to_string(), self.strict.into());
+        table.insert("sequence_length".to_string(), (self.sequence_length as i64).into());
+        table.insert("item_display_ms".to_string(), (self.item_display_ms as i64).into());
+        table.insert("direction_policy".to_string(), self.direction_policy.short_name().into());
+        table.insert("alphabet".to_string(), self.alphabet.name().into());
+        table.insert("game_mode".to_string(), self.game_mode.key().into());
+        if let Some(address) = &self.leaderboard_server {
+            table.insert("leaderboard_server".to_string(), address.as_str().into());
+        }
+        table.to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml())
+    }
+}
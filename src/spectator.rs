@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::alphabet::Alphabet;
+use crate::direction_policy::DirectionPolicy;
+use crate::game_event::GameEvent;
+use crate::game_mode::GameMode;
+use crate::input_direction::InputDirection;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::recall_test::TestVariant;
+use crate::replay::Action;
+
+// Watching a round from another machine. The player's game sends what happens as JSON lines,
+// and the viewer plays the same round along on its own copy of the engine.
+
+const PROTOCOL_VERSION: u32 = 1;
+// A viewer that can't keep up is dropped rather than holding up the player's game
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpectatorMessage {
+    Hello { version: u32, player: Option<String> },
+    Round { setup: RoundSetup },
+    Shown { index: usize },
+    Phase { phase: GamePhase },
+    Input { action: Action },
+    Finished { success: bool, score: u32 },
+}
+
+// Everything a viewer needs to generate the same round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundSetup {
+    pub seed: u64,
+    pub game_mode: GameMode,
+    pub test_variant: TestVariant,
+    pub strict: bool,
+    pub direction: InputDirection,
+    pub length: usize, // Sequence length, or stream length in n-back modes
+    pub alphabet: String,
+    pub item_display_ms: u64,
+    pub n: Option<usize>,
+    pub target_rate: Option<f64>,
+    pub accuracy_threshold: f64,
+}
+
+impl RoundSetup {
+    pub fn from_game(game: &MemoryGame, seed: u64) -> Self {
+        Self {
+            seed,
+            game_mode: game.game_mode,
+            test_variant: game.test_variant,
+            strict: !game.normal_mode,
+            direction: game.input_direction.clone(),
+            length: game.sequence.len(),
+            alphabet: game.alphabet.name(),
+            item_display_ms: (game.item_display_secs * 1000.0).round() as u64,
+            n: match game.game_mode {
+                GameMode::NBack => Some(game.n_back.block_n()),
+                GameMode::DualNBack => Some(game.dual_n_back.block_n()),
+                _ => None,
+            },
+            target_rate: match game.game_mode {
+                GameMode::NBack => Some(game.n_back.target_rate),
+                GameMode::DualNBack => Some(game.dual_n_back.target_rate),
+                _ => None,
+            },
+            accuracy_threshold: game.complex_span.accuracy_threshold,
+        }
+    }
+
+    // Starts the round on the viewer's game
+    pub fn apply(&self, game: &mut MemoryGame) -> Result<(), String> {
+        game.game_mode = self.game_mode;
+        game.test_variant = self.test_variant;
+        game.normal_mode = !self.strict;
+        game.direction_policy = DirectionPolicy::fixed(&self.direction);
+        game.sequence_length = self.length;
+        game.alphabet = Alphabet::parse(&self.alphabet)?;
+        game.item_display_secs = self.item_display_ms.max(1) as f32 / 1000.0;
+        if let Some(n) = self.n {
+            game.n_back.n = n;
+            game.dual_n_back.n = n;
+        }
+        if let Some(target_rate) = self.target_rate {
+            game.n_back.target_rate = target_rate;
+            game.dual_n_back.target_rate = target_rate;
+        }
+        game.n_back.stream_length = self.length;
+        game.dual_n_back.stream_length = self.length;
+        game.complex_span.accuracy_threshold = self.accuracy_threshold;
+
+        game.start_round(self.seed);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Viewers {
+    senders: Vec<Sender<String>>, // To each viewer's writer thread
+    round: Vec<String>,           // Messages of the round being played, for viewers who join halfway
+}
+
+// The player's side: sends the game's events to every connected viewer
+#[derive(Debug)]
+pub struct Broadcast {
+    pub address: String,
+    viewers: Arc<Mutex<Viewers>>,
+}
+
+impl Broadcast {
+    pub fn open(address: &str, player: Option<String>) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("could not listen on {}: {}", address, e))?;
+        let viewers = Arc::new(Mutex::new(Viewers::default()));
+
+        let shared = viewers.clone();
+        let hello = to_line(&SpectatorMessage::Hello { version: PROTOCOL_VERSION, player });
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+
+                // Queued under the lock, so the catch-up comes before anything sent after it
+                let (sender, receiver) = mpsc::channel::<String>();
+                {
+                    let mut viewers = shared.lock().unwrap();
+                    for line in std::iter::once(&hello).chain(&viewers.round) {
+                        let _ = sender.send(line.clone());
+                    }
+                    viewers.senders.push(sender);
+                }
+
+                // Writing happens here, so a viewer that stalls only holds up itself; its first
+                // failed write ends the thread, and the game drops it on the next send
+                thread::spawn(move || {
+                    for line in receiver {
+                        if stream.write_all(line.as_bytes()).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self { address: address.to_string(), viewers })
+    }
+
+    pub fn viewer_count(&self) -> usize {
+        self.viewers.lock().unwrap().senders.len()
+    }
+
+    pub fn publish(&self, game: &MemoryGame, events: &[GameEvent]) {
+        for event in events {
+            let message = match event {
+                GameEvent::RoundStarted { seed } => SpectatorMessage::Round { setup: RoundSetup::from_game(game, *seed) },
+                GameEvent::ItemShown(index) => SpectatorMessage::Shown { index: *index },
+                GameEvent::PhaseChanged(phase) => SpectatorMessage::Phase { phase: phase.clone() },
+                GameEvent::Input(keystroke) => SpectatorMessage::Input { action: keystroke.action.clone() },
+                GameEvent::RoundFinished(result) => SpectatorMessage::Finished { success: result.success, score: result.score },
+            };
+            self.send(&message);
+        }
+    }
+
+    fn send(&self, message: &SpectatorMessage) {
+        let line = to_line(message);
+        let mut viewers = self.viewers.lock().unwrap();
+        if matches!(message, SpectatorMessage::Round { .. }) {
+            viewers.round.clear();
+        }
+        viewers.round.push(line.clone());
+        viewers.senders.retain(|sender| sender.send(line.clone()).is_ok());
+    }
+}
+
+fn to_line(message: &SpectatorMessage) -> String {
+    format!("{}\n", serde_json::to_string(message).unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViewerStatus {
+    Connecting,
+    Watching,
+    Disconnected(String),
+}
+
+#[derive(Debug, Default)]
+struct Inbox {
+    connected: bool,
+    messages: Vec<SpectatorMessage>,
+    closed: Option<String>,
+}
+
+// The viewer's side: keeps a game in step with the one being watched
+#[derive(Debug)]
+pub struct Viewer {
+    pub address: String,
+    pub status: ViewerStatus,
+    pub player: Option<String>,
+    pub last_score: Option<u32>,
+    inbox: Arc<Mutex<Inbox>>,
+}
+
+impl Viewer {
+    pub fn connect(address: &str) -> Self {
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+
+        let shared = inbox.clone();
+        let target = address.to_string();
+        thread::spawn(move || {
+            let stream = match TcpStream::connect(&target) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    shared.lock().unwrap().closed = Some(format!("could not connect to {}: {}", target, e));
+                    return;
+                }
+            };
+            shared.lock().unwrap().connected = true;
+
+            for line in BufReader::new(stream).lines() {
+                let message = line
+                    .map_err(|e| e.to_string())
+                    .and_then(|line| serde_json::from_str(&line).map_err(|e| format!("unexpected message: {}", e)));
+                match message {
+                    Ok(message) => shared.lock().unwrap().messages.push(message),
+                    Err(e) => {
+                        shared.lock().unwrap().closed = Some(e);
+                        return;
+                    }
+                }
+            }
+            shared.lock().unwrap().closed = Some("the game being watched was closed".to_string());
+        });
+
+        Self {
+            address: address.to_string(),
+            status: ViewerStatus::Connecting,
+            player: None,
+            last_score: None,
+            inbox,
+        }
+    }
+
+    // Called every frame with the game that shows the watched round
+    pub fn poll(&mut self, game: &mut MemoryGame) {
+        let (connected, messages, closed) = {
+            let mut inbox = self.inbox.lock().unwrap();
+            (inbox.connected, std::mem::take(&mut inbox.messages), inbox.closed.take())
+        };
+        if connected && self.status == ViewerStatus::Connecting {
+            self.status = ViewerStatus::Watching;
+        }
+
+        for message in messages {
+            if let Err(e) = self.apply(message, game) {
+                self.status = ViewerStatus::Disconnected(e);
+            }
+        }
+
+        if let Some(e) = closed {
+            self.status = ViewerStatus::Disconnected(e);
+        }
+    }
+
+    fn apply(&mut self, message: SpectatorMessage, game: &mut MemoryGame) -> Result<(), String> {
+        match message {
+            SpectatorMessage::Hello { version, player } => {
+                if version != PROTOCOL_VERSION {
+                    return Err(format!("the watched game speaks spectator protocol {}", version));
+                }
+                self.player = player;
+            }
+            SpectatorMessage::Round { setup } => {
+                self.last_score = None;
+                setup.apply(game)?;
+            }
+            SpectatorMessage::Shown { index } => {
+                game.current_sequence_index = index;
+                game.sequence_display_timer = index as f32 * game.item_display_secs;
+            }
+            SpectatorMessage::Phase { phase } => {
+                if !matches!(phase, GamePhase::ShowingSequence) && game.input_start_time.is_none() {
+                    game.input_start_time = Some(Instant::now());
+                }
+                game.phase = phase;
+            }
+            // The same input on the same round gives the same state as on the player's screen
+            SpectatorMessage::Input { action } => match action {
+                Action::Item(item) => game.process_input(item),
+                Action::Delete => game.delete_last(),
+                Action::Match => game.respond_match(),
+                Action::DualMatch(stream) => game.respond_dual_match(stream),
+                Action::Equation(answer) => game.answer_equation(answer),
+                Action::Recognition(answered_same) => game.answer_recognition(answered_same),
+            },
+            SpectatorMessage::Finished { score, .. } => self.last_score = Some(score),
+        }
+        Ok(())
+    }
+}
+
+impl MemoryGame {
+    // Lets viewers on other machines watch the rounds played here
+    pub fn start_broadcast(&mut self, address: &str) -> Result<(), String> {
+        self.broadcast = Some(Broadcast::open(address, self.profile.clone())?);
+        self.publish_events = true;
+        Ok(())
+    }
+
+    // Turns this game into a read-only view of the one broadcasting on `address`
+    pub fn watch(&mut self, address: &str) {
+        self.viewer = Some(Viewer::connect(address));
+        self.phase = GamePhase::NotStarted;
+    }
+
+    pub fn poll_viewer(&mut self) {
+        if let Some(mut viewer) = self.viewer.take() {
+            viewer.poll(self);
+            self.viewer = Some(viewer);
+        }
+        // Rounds seen here belong to the player being watched
+        self.take_finished_round();
+    }
+
+    pub fn watching(&self) -> bool {
+        self.viewer.is_some()
+    }
+
+    pub fn broadcast(&self) -> Option<&Broadcast> {
+        self.broadcast.as_ref()
+    }
+
+    pub fn viewer(&self) -> Option<&Viewer> {
+        self.viewer.as_ref()
+    }
+}
@@ -0,0 +1,424 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::direction_policy::DirectionPolicy;
+use crate::memory_game::{GamePhase, MemoryGame};
+use crate::round_result::unix_timestamp;
+use crate::session::SessionInfo;
+use crate::settings::Settings;
+
+// Study designs: each participant plays every condition as one block of trials, in an order
+// decided by their participant number. A design is a TOML file such as
+//
+//   name = "speed"
+//   assignment = "latin-square"   # or "block"
+//   trials_per_block = 10
+//   seed = 42                      # block randomisation only
+//
+//   [[conditions]]
+//   name = "slow-forward"
+//   direction = "forward"
+//   item_ms = 1000
+//
+//   [[conditions]]
+//   name = "fast-reverse"
+//   direction = "reverse"
+//   item_ms = 500
+//   strict = true
+//
+// Settings a condition leaves out stay as they were when the study started.
+
+// Block randomisation goes through every order, so it's only for small designs
+const MAX_BLOCK_CONDITIONS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Assignment {
+    LatinSquare, // Balanced Latin square, one row per participant number
+    Block,       // Every order once, shuffled, per block of consecutive participant numbers
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    pub name: String,
+    pub strict: Option<bool>,
+    pub direction: Option<String>,
+    pub item_ms: Option<u64>,
+    pub length: Option<usize>,
+}
+
+impl Condition {
+    pub fn apply(&self, game: &mut MemoryGame) {
+        if let Some(strict) = self.strict {
+            game.normal_mode = !strict;
+        }
+        if let Some(policy) = self.direction.as_deref().and_then(|text| DirectionPolicy::parse(text).ok()) {
+            game.direction_policy = policy;
+        }
+        if let Some(millis) = self.item_ms {
+            game.item_display_secs = millis as f32 / 1000.0;
+        }
+        if let Some(length) = self.length {
+            game.sequence_length = length;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StudyDesign {
+    pub name: String,
+    pub assignment: Assignment,
+    pub trials_per_block: u32,
+    #[serde(default)]
+    pub seed: u64,
+    pub log: Option<PathBuf>, // Where assignments are appended, next to the design by default
+    pub conditions: Vec<Condition>,
+}
+
+impl StudyDesign {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        let mut design: StudyDesign = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        design.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        if design.log.is_none() {
+            design.log = Some(path.with_extension("assignments.jsonl"));
+        }
+        Ok(design)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.conditions.is_empty() {
+            return Err("a study needs at least one condition".to_string());
+        }
+        if self.trials_per_block == 0 {
+            return Err("trials_per_block must be at least 1".to_string());
+        }
+        if self.assignment == Assignment::Block && self.conditions.len() > MAX_BLOCK_CONDITIONS {
+            return Err(format!("block randomisation takes at most {} conditions", MAX_BLOCK_CONDITIONS));
+        }
+        for (index, condition) in self.conditions.iter().enumerate() {
+            if condition.name.trim().is_empty() {
+                return Err(format!("condition {} has no name", index + 1));
+            }
+            if self.conditions[..index].iter().any(|other| other.name == condition.name) {
+                return Err(format!("there are two conditions named '{}'", condition.name));
+            }
+            if let Some(direction) = &condition.direction {
+                DirectionPolicy::parse(direction).map_err(|e| format!("condition '{}': {}", condition.name, e))?;
+            }
+            if condition.item_ms == Some(0) || condition.length == Some(0) {
+                return Err(format!("condition '{}': item_ms and length must be at least 1", condition.name));
+            }
+        }
+        Ok(())
+    }
+
+    // Indices into conditions, in the order the participant plays them
+    pub fn order(&self, participant: u64) -> Vec<usize> {
+        match self.assignment {
+            Assignment::LatinSquare => latin_square_row(self.conditions.len(), participant),
+            Assignment::Block => {
+                let mut orders = permutations(self.conditions.len());
+                let size = orders.len() as u64;
+                let block = participant.saturating_sub(1) / size;
+                let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(block));
+                orders.shuffle(&mut rng);
+                orders.swap_remove((participant.saturating_sub(1) % size) as usize)
+            }
+        }
+    }
+}
+
+// Row of a balanced Latin square (Williams design): across participants every condition comes
+// first equally often and follows every other equally often. Odd numbers of conditions need
+// two rows per participant cycle, the second one reversed.
+fn latin_square_row(count: usize, participant: u64) -> Vec<usize> {
+    let offset = (participant.saturating_sub(1) % count as u64) as usize;
+    let (mut low, mut high) = (0, 0);
+    let mut row: Vec<usize> = (0..count)
+        .map(|position| {
+            let value = if position < 2 || position % 2 == 1 {
+                low += 1;
+                low - 1
+            } else {
+                high += 1;
+                count - high
+            };
+            (value + offset) % count
+        })
+        .collect();
+    if count % 2 == 1 && (participant.saturating_sub(1) / count as u64) % 2 == 1 {
+        row.reverse();
+    }
+    row
+}
+
+fn permutations(count: usize) -> Vec<Vec<usize>> {
+    if count == 0 {
+        return vec![vec![]];
+    }
+    let mut orders = vec![];
+    for order in permutations(count - 1) {
+        for position in 0..=order.len() {
+            let mut longer = order.clone();
+            longer.insert(position, count - 1);
+            orders.push(longer);
+        }
+    }
+    orders.sort();
+    orders
+}
+
+// The number in a participant ID such as P07 or s-12
+pub fn participant_number(participant_id: &str) -> Result<u64, String> {
+    let digits: String = participant_id.chars().filter(|c| c.is_ascii_digit()).collect();
+    match digits.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err("the study needs a number in the participant ID, e.g. P7".to_string()),
+    }
+}
+
+// One line of the assignment log
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignmentRecord {
+    pub timestamp: u64,
+    pub study: String,
+    pub assignment: Assignment,
+    pub participant_id: String,
+    pub participant_number: u64,
+    pub session: u32,
+    pub order: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StudyStage {
+    Break,   // Before a block; the participant starts it when ready
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct Study {
+    pub design: StudyDesign,
+    pub order: Vec<usize>,
+    pub block: usize,        // Index into order
+    pub trials_done: u32,    // In the current block
+    pub stage: StudyStage,
+    settings_before: Settings,
+}
+
+impl Study {
+    pub fn block_count(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn condition(&self) -> &Condition {
+        &self.design.conditions[self.order[self.block.min(self.order.len() - 1)]]
+    }
+}
+
+impl MemoryGame {
+    // The design is used from the next session on; participants are assigned when it starts
+    pub fn set_study_design(&mut self, design: Option<StudyDesign>) {
+        self.study_design = design;
+    }
+
+    // Works out the participant's order, logs it and waits before the first block
+    pub(crate) fn start_study(&mut self, session: &SessionInfo) -> Result<(), String> {
+        let Some(design) = self.study_design.clone() else {
+            return Ok(());
+        };
+        let number = participant_number(&session.participant_id)?;
+        let order = design.order(number);
+
+        let record = AssignmentRecord {
+            timestamp: unix_timestamp(),
+            study: design.name.clone(),
+            assignment: design.assignment,
+            participant_id: session.participant_id.clone(),
+            participant_number: number,
+            session: session.session,
+            order: order.iter().map(|&index| design.conditions[index].name.clone()).collect(),
+        };
+        if let Some(path) = &design.log {
+            append_record(path, &record).map_err(|e| format!("could not log the assignment to {}: {}", path.display(), e))?;
+        }
+
+        // A study started over another one keeps the settings from before both
+        let settings_before = match self.study.take() {
+            Some(study) => study.settings_before,
+            None => Settings::from_game(self),
+        };
+        self.study = Some(Study { design, order, block: 0, trials_done: 0, stage: StudyStage::Break, settings_before });
+        Ok(())
+    }
+
+    pub fn study(&self) -> Option<&Study> {
+        self.study.as_ref()
+    }
+
+    // Counts a finished trial; the last one of a block leads to a break or the end
+    pub fn record_study_trial(&mut self) {
+        let Some(study) = &mut self.study else {
+            return;
+        };
+        if study.stage != StudyStage::Running {
+            return;
+        }
+        study.trials_done += 1;
+        if study.trials_done < study.design.trials_per_block {
+            return;
+        }
+        study.trials_done = 0;
+        study.block += 1;
+        if study.block < study.block_count() {
+            study.stage = StudyStage::Break;
+        } else {
+            study.stage = StudyStage::Finished;
+        }
+    }
+
+    // Starts the next trial, or the next block after a break
+    pub fn next_study_trial(&mut self) {
+        let Some(study) = &mut self.study else {
+            self.start_game();
+            return;
+        };
+        match study.stage {
+            StudyStage::Finished => return,
+            StudyStage::Break => {
+                study.stage = StudyStage::Running;
+                let condition = study.condition().clone();
+                // Nothing carries over from the block before
+                study.settings_before.clone().apply(self);
+                condition.apply(self);
+                if let Some(session) = &mut self.session {
+                    session.condition = Some(condition.name);
+                }
+            }
+            StudyStage::Running => {}
+        }
+        self.start_game();
+    }
+
+    // Back to the settings from before the study
+    pub fn leave_study(&mut self) {
+        if let Some(study) = self.study.take() {
+            study.settings_before.apply(self);
+        }
+        self.phase = GamePhase::NotStarted;
+    }
+}
+
+fn append_record(path: &Path, record: &AssignmentRecord) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn design(assignment: Assignment, count: usize) -> StudyDesign {
+        StudyDesign {
+            name: "test".to_string(),
+            assignment,
+            trials_per_block: 1,
+            seed: 7,
+            log: None,
+            conditions: (0..count)
+                .map(|index| Condition { name: format!("c{}", index), strict: None, direction: None, item_ms: None, length: None })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn latin_square_rows_balance_positions_and_carryover() {
+        for count in 1..=8 {
+            // Odd designs take a row and its reverse to balance
+            let cycle = if count % 2 == 0 { count } else { 2 * count };
+            let rows: Vec<Vec<usize>> = (1..=cycle as u64).map(|participant| latin_square_row(count, participant)).collect();
+
+            let mut at_position = vec![vec![0; count]; count];
+            let mut followed_by = vec![vec![0; count]; count];
+            for row in &rows {
+                let mut sorted = row.clone();
+                sorted.sort();
+                assert_eq!(sorted, (0..count).collect::<Vec<_>>(), "{:?} isn't an order of {}", row, count);
+                for (position, condition) in row.iter().enumerate() {
+                    at_position[position][*condition] += 1;
+                }
+                for pair in row.windows(2) {
+                    followed_by[pair[0]][pair[1]] += 1;
+                }
+            }
+
+            let rounds = cycle / count;
+            assert!(at_position.iter().flatten().all(|times| *times == rounds), "positions of {}: {:?}", count, at_position);
+            for (first, next) in followed_by.iter().enumerate() {
+                for (second, times) in next.iter().enumerate() {
+                    let expected = if first == second { 0 } else { rounds };
+                    assert_eq!(*times, expected, "{} after {} in a design of {}", second, first, count);
+                }
+            }
+
+            // The next cycle starts over
+            assert_eq!(latin_square_row(count, cycle as u64 + 1), rows[0]);
+        }
+    }
+
+    #[test]
+    fn permutations_are_every_order_once() {
+        assert_eq!(permutations(0), vec![Vec::<usize>::new()]);
+        assert_eq!(permutations(3), [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]]);
+        let mut orders = permutations(5);
+        assert_eq!(orders.len(), 120);
+        orders.dedup();
+        assert_eq!(orders.len(), 120);
+    }
+
+    #[test]
+    fn each_block_of_participants_gets_every_order_once() {
+        let design = design(Assignment::Block, 3);
+        for block in 0..3u64 {
+            let mut orders: Vec<Vec<usize>> = (1..=6).map(|number| design.order(block * 6 + number)).collect();
+            orders.sort();
+            assert_eq!(orders, permutations(3));
+        }
+
+        // The same seed gives the same assignments, another seed shuffles them differently
+        let first: Vec<Vec<usize>> = (1..=12).map(|number| design.order(number)).collect();
+        assert_eq!(first, (1..=12).map(|number| design.order(number)).collect::<Vec<_>>());
+        let reseeded = StudyDesign { seed: 8, ..design };
+        assert_ne!(first, (1..=12).map(|number| reseeded.order(number)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn participant_numbers_come_from_the_digits_in_the_id() {
+        assert_eq!(participant_number("P07"), Ok(7));
+        assert_eq!(participant_number("s-12"), Ok(12));
+        assert!(participant_number("P0").is_err());
+        assert!(participant_number("anon").is_err());
+    }
+
+    #[test]
+    fn designs_that_cant_be_run_are_rejected() {
+        assert!(design(Assignment::LatinSquare, 3).validate().is_ok());
+        assert!(design(Assignment::LatinSquare, 0).validate().is_err());
+        assert!(design(Assignment::Block, MAX_BLOCK_CONDITIONS + 1).validate().is_err());
+
+        let mut twice = design(Assignment::LatinSquare, 2);
+        twice.conditions[1].name = "c0".to_string();
+        assert!(twice.validate().is_err());
+        let mut no_trials = design(Assignment::LatinSquare, 2);
+        no_trials.trials_per_block = 0;
+        assert!(no_trials.validate().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use memory_game::event_feed::EventFeed;
+use memory_game::game_event::GameEvent;
+use memory_game::memory_game::MemoryGame;
+
+// Reads one unmasked server frame and returns its opcode and payload
+fn read_frame(stream: &mut impl Read) -> (u8, Vec<u8>) {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).unwrap();
+    assert_eq!(header[1] & 0x80, 0, "servers don't mask frames");
+    let length = match header[1] & 0x7f {
+        126 => {
+            let mut extended = [0u8; 2];
+            stream.read_exact(&mut extended).unwrap();
+            u16::from_be_bytes(extended) as usize
+        }
+        127 => {
+            let mut extended = [0u8; 8];
+            stream.read_exact(&mut extended).unwrap();
+            u64::from_be_bytes(extended) as usize
+        }
+        length => length as usize,
+    };
+    let mut payload = vec![0; length];
+    stream.read_exact(&mut payload).unwrap();
+    (header[0], payload)
+}
+
+// Connects and goes through the handshake, checking the server's answer
+fn connect(feed: &EventFeed) -> BufReader<TcpStream> {
+    let clients = feed.client_count();
+    let mut stream = TcpStream::connect(&feed.address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    // The key and accept value from RFC 6455, section 1.3
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        feed.address
+    )
+    .unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut response = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            break;
+        }
+        response.push(line.trim_end().to_string());
+    }
+    assert_eq!(response[0], "HTTP/1.1 101 Switching Protocols");
+    assert!(response.contains(&"Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string()));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while feed.client_count() == clients {
+        assert!(Instant::now() < deadline, "the client was never added");
+        thread::sleep(Duration::from_millis(5));
+    }
+    reader
+}
+
+#[test]
+fn local_client_gets_the_handshake_and_events() {
+    let feed = EventFeed::open("127.0.0.1:0").unwrap();
+    let mut reader = connect(&feed);
+
+    // A long player name takes the round_started message past the 125 byte short length
+    let mut game = MemoryGame::new();
+    game.profile = Some("p".repeat(120));
+    game.start_round(1);
+    feed.publish(&game, &[GameEvent::RoundStarted { seed: 1 }, GameEvent::ItemShown(0)]);
+
+    let (opcode, payload) = read_frame(&mut reader);
+    assert_eq!(opcode, 0x81);
+    assert!(payload.len() > 125);
+    let started: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert_eq!(started["version"], 1);
+    assert_eq!(started["event"], "round_started");
+    assert_eq!(started["length"], game.sequence().len());
+
+    let (opcode, payload) = read_frame(&mut reader);
+    assert_eq!(opcode, 0x81);
+    let shown: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert_eq!(shown["event"], "item_shown");
+    assert_eq!(shown["item"], game.sequence()[0].as_char().to_string());
+}
+
+#[test]
+fn a_client_that_stops_reading_doesnt_hold_up_the_game() {
+    // Far more than the socket buffers hold, in messages of about 100 kB
+    let mut game = MemoryGame::new();
+    game.profile = Some("p".repeat(100_000));
+    game.start_round(1);
+    let publish_all = |feed: &EventFeed| {
+        let started = Instant::now();
+        for _ in 0..100 {
+            feed.publish(&game, &[GameEvent::RoundStarted { seed: 1 }]);
+        }
+        started.elapsed()
+    };
+
+    // Building the messages takes its time on its own, measured with nobody connected
+    let unwatched = publish_all(&EventFeed::open("127.0.0.1:0").unwrap());
+
+    let feed = EventFeed::open("127.0.0.1:0").unwrap();
+    let _stalled: Vec<_> = (0..3).map(|_| connect(&feed)).collect();
+    let stalled = publish_all(&feed);
+
+    // Writing under the game's hand would wait out a timeout for each of the three
+    assert!(stalled < unwatched + Duration::from_millis(300), "{:?} against {:?}", stalled, unwatched);
+}
@@ -0,0 +1,67 @@
+use memory_game::digit_span::{DigitSpanProtocol, DigitSpanStage};
+use memory_game::input_direction::InputDirection;
+use memory_game::memory_game::{GamePhase, MemoryGame};
+use memory_game::quest::QuestParameter;
+use memory_game::round_result::RoundResult;
+
+// Plays the round on screen with the right answer and hands back its result
+fn answer(game: &mut MemoryGame) -> RoundResult {
+    while matches!(game.phase(), GamePhase::ShowingSequence) {
+        game.advance(0.05);
+    }
+    for item in game.expected_input() {
+        game.process_input(item);
+    }
+    game.take_finished_round().unwrap()
+}
+
+#[test]
+fn skipping_a_digit_span_trial_keeps_the_battery_where_it_was() {
+    let mut game = MemoryGame::new();
+    game.open_experimenter_console();
+    let protocol = DigitSpanProtocol {
+        directions: vec!["backward".to_string()],
+        start_length: 3,
+        trials_per_length: 2,
+        item_ms: 500,
+        ..DigitSpanProtocol::default()
+    };
+    game.start_digit_span(protocol).unwrap();
+
+    game.start_trial();
+    let result = answer(&mut game);
+    game.record_digit_span_trial(&result);
+    game.start_trial();
+    game.skip_trial();
+
+    // Still the second trial at length 3, backwards, with nothing booked for the skipped one
+    let digit_span = game.digit_span().unwrap();
+    assert_eq!(digit_span.stage, DigitSpanStage::Running);
+    assert_eq!((digit_span.length, digit_span.trial), (3, 1));
+    assert_eq!(digit_span.scores[0].trials, vec![(3, true)]);
+    assert!(game.in_round());
+    assert_eq!(game.sequence().len(), 3);
+    assert_eq!(*game.input_direction(), InputDirection::Reverse);
+    assert_eq!(game.item_display_secs, 0.5);
+}
+
+#[test]
+fn skipping_a_quest_trial_keeps_the_estimate_where_it_was() {
+    let mut game = MemoryGame::new();
+    game.open_experimenter_console();
+    game.start_quest(QuestParameter::Length, 0.75).unwrap();
+
+    game.start_trial();
+    let result = answer(&mut game);
+    game.record_quest_trial(&result);
+    let current = game.quest().unwrap().current;
+    let trials = game.quest().unwrap().trials.len();
+
+    game.start_trial();
+    game.skip_trial();
+
+    let quest = game.quest().unwrap();
+    assert_eq!(quest.trials.len(), trials);
+    assert_eq!(quest.current, current);
+    assert_eq!(game.sequence().len(), current as usize);
+}
@@ -0,0 +1,55 @@
+use memory_game::direction_policy::DirectionPolicy;
+use memory_game::game_mode::GameMode;
+use memory_game::hot_seat::{HotSeatPlayer, HotSeatSetup};
+use memory_game::input_direction::InputDirection;
+use memory_game::memory_game::{GamePhase, MemoryGame};
+
+fn setup() -> HotSeatSetup {
+    HotSeatSetup {
+        players: vec![
+            HotSeatPlayer::new("Alice".to_string(), None),
+            HotSeatPlayer::new("Bob".to_string(), None),
+            HotSeatPlayer::new("Carol".to_string(), None),
+        ],
+        ..HotSeatSetup::default()
+    }
+}
+
+// Plays the current player's turn with the right answer
+fn play_turn(game: &mut MemoryGame) -> InputDirection {
+    game.start_hot_seat_turn();
+    let direction = game.input_direction().clone();
+    while matches!(game.phase(), GamePhase::ShowingSequence) {
+        game.advance(0.05);
+    }
+    for item in game.expected_input() {
+        game.process_input(item);
+    }
+    let result = game.take_finished_round().unwrap();
+    game.hot_seat_mut().unwrap().record(&result);
+    direction
+}
+
+#[test]
+fn alternating_directions_switch_between_rounds_not_turns() {
+    let mut game = MemoryGame::new();
+    game.direction_policy = DirectionPolicy::Alternate;
+    game.start_hot_seat(&setup()).unwrap();
+
+    for expected in [InputDirection::Forward, InputDirection::Reverse, InputDirection::Forward] {
+        for _ in 0..3 {
+            assert_eq!(play_turn(&mut game), expected);
+        }
+        game.hot_seat_mut().unwrap().continue_after_summary();
+    }
+}
+
+#[test]
+fn n_back_modes_cant_be_played_in_hot_seat() {
+    let mut game = MemoryGame::new();
+    for mode in [GameMode::NBack, GameMode::DualNBack] {
+        game.game_mode = mode;
+        assert!(game.start_hot_seat(&setup()).is_err());
+        assert!(game.hot_seat().is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use memory_game::game_mode::GameMode;
+use memory_game::leaderboard::Filter;
+use memory_game::leaderboard_client::{fetch_rankings, submit};
+use memory_game::leaderboard_server::LeaderboardServer;
+use memory_game::memory_game::{GamePhase, MemoryGame};
+use memory_game::replay::{input_duration_ms, verify};
+use memory_game::round_result::RoundResult;
+
+// Whether an equation such as "7 × 3 = 22" holds
+fn equation_holds(text: &str) -> bool {
+    let parts: Vec<&str> = text.split(' ').collect();
+    let (left, right): (i32, i32) = (parts[0].parse().unwrap(), parts[2].parse().unwrap());
+    let shown: i32 = parts[4].parse().unwrap();
+    let result = match parts[1] {
+        "+" => left + right,
+        "-" => left - right,
+        _ => left * right,
+    };
+    result == shown
+}
+
+// Plays a round through to the end, giving the right answers if the mode takes any
+fn play(game: &mut MemoryGame, seed: u64) -> RoundResult {
+    game.start_round(seed);
+    while matches!(game.phase(), GamePhase::ShowingSequence) {
+        if let Some(equation) = game.complex_span.pending_equation(game.current_sequence_index()) {
+            game.answer_equation(equation_holds(&equation.text()));
+        }
+        game.advance(0.05);
+    }
+    for item in game.expected_input() {
+        game.process_input(item);
+    }
+    let mut result = game.take_finished_round().expect("the round finished");
+    result.profile = Some("Alice".to_string());
+    result
+}
+
+// Each server gets its own file, as the tests run side by side
+static SERVERS: AtomicUsize = AtomicUsize::new(0);
+
+fn start_server() -> String {
+    let server = SERVERS.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("memory_game_server_{}_{}.jsonl", std::process::id(), server));
+    let _ = std::fs::remove_file(&path);
+    let mut server = LeaderboardServer::open(path).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || server.serve(listener));
+    address
+}
+
+#[test]
+fn submitted_rounds_are_replayed_over_localhost() {
+    let address = start_server();
+    let mut game = MemoryGame::new();
+    let result = play(&mut game, 42);
+    assert!(result.success);
+
+    assert_eq!(submit(&address, &result), Ok(1));
+    let rankings = fetch_rankings(&address, &Filter::default()).unwrap();
+    assert_eq!(rankings.len(), 1);
+    assert_eq!(rankings[0].seed, 42);
+
+    // The same round again, even under a new time, is turned away
+    let again = RoundResult { timestamp: result.timestamp + 60, ..result.clone() };
+    assert!(submit(&address, &again).unwrap_err().contains("already submitted"));
+
+    // So is a score the keystrokes don't give
+    let mut forged = play(&mut game, 7);
+    forged.score += 10;
+    assert!(submit(&address, &forged).unwrap_err().contains("replay failed"));
+
+    // Failed rounds aren't ranked, so there is nothing to submit them for
+    let failed = RoundResult { success: false, ..play(&mut game, 8) };
+    assert!(submit(&address, &failed).unwrap_err().contains("only correct rounds"));
+}
+
+#[test]
+fn rounds_replay_with_their_own_target_rate_and_accuracy_threshold() {
+    let mut game = MemoryGame::new();
+    game.game_mode = GameMode::NBack;
+    game.n_back.target_rate = 0.5;
+    let result = play(&mut game, 3);
+    assert_eq!(result.target_rate, Some(0.5));
+    assert_eq!(verify(&result), Ok(()));
+    // At the default rate the seed gives a different stream
+    assert!(verify(&RoundResult { target_rate: None, ..result }).is_err());
+
+    let mut game = MemoryGame::new();
+    game.game_mode = GameMode::ComplexSpan;
+    game.complex_span.accuracy_threshold = 0.5;
+    game.start_round(5);
+    let mut answered = 0;
+    while matches!(game.phase(), GamePhase::ShowingSequence) {
+        // Three of the four equations right, which is under the default threshold
+        if let Some(equation) = game.complex_span.pending_equation(game.current_sequence_index()) {
+            let holds = equation_holds(&equation.text());
+            game.answer_equation(if answered == 0 { !holds } else { holds });
+            answered += 1;
+        }
+        game.advance(0.05);
+    }
+    for item in game.expected_input() {
+        game.process_input(item);
+    }
+    let result = game.take_finished_round().unwrap();
+    assert_eq!(result.accuracy_threshold, Some(0.5));
+    assert!(result.success);
+    assert_eq!(verify(&result), Ok(()));
+    assert!(verify(&RoundResult { accuracy_threshold: None, ..result }).is_err());
+}
+
+#[test]
+fn rounds_with_settings_the_game_doesnt_offer_are_turned_away() {
+    let address = start_server();
+    let mut game = MemoryGame::new();
+    game.game_mode = GameMode::NBack;
+    let n_back = play(&mut game, 11);
+    let length = n_back.length();
+
+    let rejected = [
+        ("target rate", RoundResult { target_rate: Some(5.0), ..n_back.clone() }),
+        ("target rate", RoundResult { target_rate: Some(0.0), ..n_back.clone() }),
+        ("N must", RoundResult { n: Some(0), ..n_back.clone() }),
+        ("N must", RoundResult { n: Some(length), ..n_back.clone() }),
+        ("items, more than", RoundResult { sequence: "1".repeat(101), ..n_back.clone() }),
+    ];
+
+    let mut game = MemoryGame::new();
+    game.game_mode = GameMode::ComplexSpan;
+    let complex_span = play(&mut game, 12);
+    let rejected = rejected.into_iter().chain([
+        ("accuracy threshold", RoundResult { accuracy_threshold: Some(0.4), ..complex_span.clone() }),
+        ("accuracy threshold", RoundResult { accuracy_threshold: Some(1.5), ..complex_span.clone() }),
+    ]);
+
+    for (reason, result) in rejected {
+        let error = submit(&address, &result).unwrap_err();
+        assert!(error.contains(reason), "{}", error);
+    }
+
+    // The server is still up for rounds that are fine
+    assert_eq!(submit(&address, &n_back), Ok(1));
+}
+
+#[test]
+fn input_time_comes_from_the_keystroke_log() {
+    let address = start_server();
+    let mut game = MemoryGame::new();
+    let mut result = play(&mut game, 21);
+
+    // Five answers a second apart, claimed to have taken a millisecond
+    for (i, keystroke) in result.keystrokes.iter_mut().enumerate() {
+        keystroke.ms = 3000 + 1000 * i as u64;
+    }
+    result.input_duration_ms = Some(1);
+    assert_eq!(input_duration_ms(&result.keystrokes), Some(4000));
+
+    assert_eq!(submit(&address, &result), Ok(1));
+    let rankings = fetch_rankings(&address, &Filter::default()).unwrap();
+    assert_eq!(rankings[0].input_duration_ms, Some(4000));
+}
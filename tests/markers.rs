@@ -0,0 +1,71 @@
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use memory_game::char_type::CharType;
+use memory_game::game_event::GameEvent;
+use memory_game::markers::{MarkerCodes, MarkerStream};
+use memory_game::replay::{Action, Keystroke};
+
+#[test]
+fn markers_arrive_at_a_udp_listener() {
+    let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+    listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let codes = MarkerCodes { item_onset: 128, ..MarkerCodes::default() };
+    let mut markers = MarkerStream::open(&listener.local_addr().unwrap().to_string(), codes).unwrap();
+
+    // A frame showing item 0, one with it gone and the answer phase open, then a keystroke
+    let round_start = Instant::now();
+    markers.queue(&[GameEvent::RoundStarted { seed: 1 }], Some(round_start));
+    markers.frame(&[0], false);
+    markers.frame(&[], true);
+    markers.queue(
+        &[GameEvent::Input(Keystroke { ms: 900, item: None, action: Action::Item(CharType::Number(7)) })],
+        Some(round_start),
+    );
+    markers.frame(&[], true);
+
+    let mut received = vec![];
+    let mut buffer = [0u8; 512];
+    for _ in 0..5 {
+        let length = listener.recv(&mut buffer).unwrap();
+        received.push(serde_json::from_slice::<serde_json::Value>(&buffer[..length]).unwrap());
+    }
+
+    let kinds: Vec<&str> = received.iter().map(|marker| marker["marker"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["round_start", "item_onset", "item_offset", "input_start", "keystroke"]);
+    let numbers: Vec<u64> = received.iter().map(|marker| marker["number"].as_u64().unwrap()).collect();
+    assert_eq!(numbers, [1, 2, 3, 4, 5]);
+    assert_eq!(received[1]["code"], 128);
+    assert_eq!(received[1]["item"], 0);
+    assert_eq!(received[2]["code"], 11);
+    assert_eq!(received[2]["item"], 0);
+    assert_eq!(received[4]["code"], 30);
+
+    // The keystroke is stamped with when it was logged, not when the queue went out
+    let since_start = received[4]["t"].as_f64().unwrap() - received[0]["t"].as_f64().unwrap();
+    assert!((since_start - 0.9).abs() < 1e-6, "{}", since_start);
+}
+
+#[test]
+fn keystrokes_before_a_new_round_keep_the_old_rounds_start() {
+    let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+    listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut markers = MarkerStream::open(&listener.local_addr().unwrap().to_string(), MarkerCodes::default()).unwrap();
+
+    let first = Instant::now();
+    let second = first + Duration::from_secs(10);
+    markers.queue(&[GameEvent::RoundStarted { seed: 1 }], Some(first));
+    let keystroke = |ms| GameEvent::Input(Keystroke { ms, item: None, action: Action::Delete });
+    // The last keystroke of the first round and the next round's first, in one frame
+    markers.queue(&[keystroke(2000), GameEvent::RoundStarted { seed: 2 }, keystroke(500)], Some(second));
+    markers.frame(&[], false);
+
+    let mut t = vec![];
+    let mut buffer = [0u8; 512];
+    for _ in 0..4 {
+        let length = listener.recv(&mut buffer).unwrap();
+        t.push(serde_json::from_slice::<serde_json::Value>(&buffer[..length]).unwrap()["t"].as_f64().unwrap());
+    }
+    let offsets: Vec<f64> = t.iter().map(|time| ((time - t[0]) * 1000.0).round()).collect();
+    assert_eq!(offsets, [0.0, 2000.0, 10_000.0, 10_500.0]);
+}
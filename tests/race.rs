@@ -0,0 +1,64 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use memory_game::memory_game::{GamePhase, MemoryGame};
+use memory_game::race::{RaceOutcome, RaceStage};
+
+fn poll_until(host: &mut MemoryGame, joiner: &mut MemoryGame, done: impl Fn(&MemoryGame, &MemoryGame) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !done(host, joiner) {
+        assert!(Instant::now() < deadline, "host {:?}, joiner {:?}", host.race().unwrap().stage, joiner.race().unwrap().stage);
+        host.poll_race();
+        joiner.poll_race();
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+// Enters the race round correctly and reports the finish, as the GUI does
+fn finish(game: &mut MemoryGame) {
+    while matches!(game.phase(), GamePhase::ShowingSequence) {
+        game.advance(0.1);
+    }
+    for item in game.expected_input() {
+        game.process_input(item);
+        game.poll_race();
+    }
+    let result = game.take_finished_round().expect("the round finished");
+    assert!(result.success);
+    game.finish_race(&result);
+}
+
+#[test]
+fn race_over_loopback() {
+    let mut host = MemoryGame::new();
+    let mut joiner = MemoryGame::new();
+    host.host_race("127.0.0.1:0").unwrap();
+    joiner.join_race(&host.race().unwrap().address.clone());
+
+    // Hello both ways
+    poll_until(&mut host, &mut joiner, |host, joiner| {
+        host.race().unwrap().opponent_name.is_some() && joiner.race().unwrap().opponent_name.is_some()
+    });
+    assert_eq!(host.race().unwrap().stage, RaceStage::Lobby);
+    assert_eq!(joiner.race().unwrap().opponent_name.as_deref(), Some("Guest"));
+
+    // Start, then the countdown on both sides
+    host.start_race().unwrap();
+    poll_until(&mut host, &mut joiner, |host, joiner| {
+        host.race().unwrap().stage == RaceStage::Racing && joiner.race().unwrap().stage == RaceStage::Racing
+    });
+    assert_eq!(host.sequence(), joiner.sequence());
+
+    // The joiner finishes first by its own clock
+    finish(&mut joiner);
+    thread::sleep(Duration::from_millis(100));
+    finish(&mut host);
+
+    poll_until(&mut host, &mut joiner, |host, joiner| {
+        matches!(host.race().unwrap().stage, RaceStage::Done(_)) && matches!(joiner.race().unwrap().stage, RaceStage::Done(_))
+    });
+    let length = joiner.sequence().len();
+    assert_eq!(host.race().unwrap().opponent_progress, length);
+    assert_eq!(host.race().unwrap().stage, RaceStage::Done(RaceOutcome::Lost));
+    assert_eq!(joiner.race().unwrap().stage, RaceStage::Done(RaceOutcome::Won));
+}